@@ -1,6 +1,8 @@
 use bevy::prelude::*;
 use bevy_kira_audio::AudioChannel;
 
+use crate::game::{difficulty::Difficulty, GameMode};
+
 use super::{
     loading::{Background, MusicAssets, UiAssets},
     AppState,
@@ -15,7 +17,8 @@ impl Plugin for MainMenuPlugin {
             .add_system(main_menu_music.in_schedule(OnEnter(AppState::MainMenu)))
             .add_system(main_menu_system.in_set(OnUpdate(AppState::MainMenu)))
             .add_system(main_menu_cleanup.in_schedule(OnExit(AppState::MainMenu)))
-            .add_system(main_menu_bob_start.in_set(OnUpdate(AppState::MainMenu)));
+            .add_system(main_menu_bob_start.in_set(OnUpdate(AppState::MainMenu)))
+            .add_system(main_menu_difficulty_system.in_set(OnUpdate(AppState::MainMenu)));
     }
 }
 
@@ -27,10 +30,14 @@ pub struct MenuUiData {
 #[derive(Default, Component)]
 pub struct PressSpaceMarker;
 
+#[derive(Default, Component)]
+pub struct DifficultyText;
+
 pub fn main_menu_setup(
     mut commands: Commands,
-    _asset_server: Res<AssetServer>,
+    asset_server: Res<AssetServer>,
     ui_assets: Res<UiAssets>,
+    difficulty: Res<Difficulty>,
 ) {
     // root node
     let ui_entity = commands
@@ -93,6 +100,67 @@ pub fn main_menu_setup(
                     });
                 });
         })
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "A: Arena",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(100.0),
+                        right: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                }),
+            );
+            parent.spawn(
+                TextBundle::from_section(
+                    "R: Random run",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(20.0),
+                        right: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                }),
+            );
+            parent
+                .spawn(
+                    TextBundle::from_section(
+                        difficulty_label(&difficulty),
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            bottom: Val::Px(60.0),
+                            right: Val::Px(20.0),
+                            ..default()
+                        },
+                        ..default()
+                    }),
+                )
+                .insert(DifficultyText);
+        })
         .id();
     commands.insert_resource(MenuUiData { ui_entity });
 }
@@ -100,12 +168,40 @@ pub fn main_menu_setup(
 pub fn main_menu_system(
     mut next_state: ResMut<NextState<AppState>>,
     keyboard_input: Res<Input<KeyCode>>,
+    mut game_mode: ResMut<GameMode>,
 ) {
     if keyboard_input.just_pressed(KeyCode::Space) {
+        *game_mode = GameMode::Campaign;
+        next_state.set(AppState::InGame);
+    }
+    if keyboard_input.just_pressed(KeyCode::R) {
+        *game_mode = GameMode::Procedural;
+        next_state.set(AppState::InGame);
+    }
+    if keyboard_input.just_pressed(KeyCode::A) {
+        *game_mode = GameMode::Arena;
         next_state.set(AppState::InGame);
     }
 }
 
+fn difficulty_label(difficulty: &Difficulty) -> String {
+    format!("D: Difficulty - {}", difficulty.preset.name())
+}
+
+// Cycle through the difficulty presets
+pub fn main_menu_difficulty_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut text_query: Query<&mut Text, With<DifficultyText>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::D) {
+        difficulty.next_preset();
+        for mut text in text_query.iter_mut() {
+            text.sections[0].value = difficulty_label(&difficulty);
+        }
+    }
+}
+
 pub fn main_menu_cleanup(mut commands: Commands, menu_data: Res<MenuUiData>) {
     commands.entity(menu_data.ui_entity).despawn_recursive();
 }
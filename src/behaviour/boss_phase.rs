@@ -0,0 +1,181 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use seldom_state::prelude::*;
+
+use crate::{
+    entity::{
+        creature::{damage_invulnerability_system, Creature},
+        projectile::RangedAttack,
+    },
+    game::GameState,
+};
+
+use super::{
+    separation::Separation, states::phase_transition::PhaseTransition, target::TargetRole,
+    triggers::PhaseThreshold,
+};
+
+// How much faster an enraged boss moves and attacks
+const ENRAGE_SPEED_MULTIPLIER: f32 = 1.5;
+const ENRAGE_COOLDOWN_MULTIPLIER: f32 = 0.5;
+
+pub struct BossPhasePlugin;
+
+impl Plugin for BossPhasePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            boss_enrage_system
+                .in_set(OnUpdate(GameState::InLevel))
+                .after(damage_invulnerability_system),
+        )
+        .add_system(boss_phase_cleanup.in_schedule(OnExit(GameState::InLevel)));
+    }
+}
+
+// One stage of a boss fight, with its own state machine and attacks
+#[derive(Clone)]
+pub struct BossPhase {
+    // The phase starts once the boss is at or below this fraction of its max health
+    pub health_threshold: f32,
+    pub state_machine: fn(TargetRole) -> StateMachine,
+    pub ranged_attack: Option<RangedAttack>,
+    // Relative to the boss' speed when it was spawned
+    pub speed_multiplier: f32,
+}
+
+// Bosses move through their phases in order as they lose health. Each change plays a
+// `PhaseTransition` first, and the whole fight is on an enrage timer
+#[derive(Component)]
+pub struct BossPhases {
+    pub target: TargetRole,
+    pub phases: Vec<BossPhase>,
+    current: usize,
+    pub enrage_timer: Timer,
+    pub enraged: bool,
+}
+
+impl BossPhases {
+    pub fn new(target: TargetRole, phases: Vec<BossPhase>, enrage_seconds: f32) -> Self {
+        Self {
+            target,
+            phases,
+            current: 0,
+            enrage_timer: Timer::from_seconds(enrage_seconds, TimerMode::Once),
+            enraged: false,
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    // The phase the boss should move on to at the given health fraction, if any
+    pub fn next_phase(&self, health_fraction: f32) -> Option<usize> {
+        let next = self.current + 1;
+        self.phases
+            .get(next)
+            .filter(|phase| health_fraction <= phase.health_threshold)
+            .map(|_| next)
+    }
+
+    // Inserts the phase's state machine and attacks, every phase can be interrupted by the next
+    pub fn insert_phase(&self, entity_commands: &mut EntityCommands, phase: usize) {
+        let phase = &self.phases[phase];
+        entity_commands.insert(
+            (phase.state_machine)(self.target)
+                .trans::<AnyState>(PhaseThreshold, PhaseTransition::default()),
+        );
+
+        match &phase.ranged_attack {
+            Some(ranged_attack) => {
+                let mut ranged_attack = ranged_attack.clone();
+                if self.enraged {
+                    enrage_cooldown(&mut ranged_attack);
+                }
+                entity_commands.insert(ranged_attack);
+            }
+            None => {
+                entity_commands.remove::<RangedAttack>();
+            }
+        }
+    }
+
+    // Moves on to the next phase, swapping out the state machine and adjusting the speed
+    pub fn advance(
+        &mut self,
+        entity_commands: &mut EntityCommands,
+        creature: &mut Creature,
+        separation: Option<&mut Separation>,
+        phase: usize,
+    ) {
+        let speed_ratio =
+            self.phases[phase].speed_multiplier / self.phases[self.current].speed_multiplier;
+        scale_speed(creature, separation, speed_ratio);
+        self.current = phase;
+
+        // Re-adding the state machine makes it start over from the new graph's initial state
+        entity_commands.remove::<StateMachine>();
+        self.insert_phase(entity_commands, phase);
+    }
+}
+
+// Separation resets the max speed once the boss is no longer crowded, so that has to keep up too
+fn scale_speed(creature: &mut Creature, separation: Option<&mut Separation>, multiplier: f32) {
+    creature.max_speed *= multiplier;
+    if let Some(separation) = separation {
+        if let Some(max_speed_reset) = separation.max_speed_reset {
+            separation.max_speed_reset = Some(max_speed_reset * multiplier);
+        }
+    }
+}
+
+fn enrage_cooldown(ranged_attack: &mut RangedAttack) {
+    let duration = ranged_attack
+        .cooldown
+        .duration()
+        .mul_f32(ENRAGE_COOLDOWN_MULTIPLIER);
+    ranged_attack.cooldown.set_duration(duration);
+}
+
+// Bosses that take too long to kill get faster and angrier for the rest of the fight
+pub fn boss_enrage_system(
+    time: Res<Time>,
+    mut boss_query: Query<
+        (
+            &mut BossPhases,
+            &mut Creature,
+            &mut TextureAtlasSprite,
+            Option<&mut RangedAttack>,
+            Option<&mut Separation>,
+        ),
+        Without<PhaseTransition>,
+    >,
+) {
+    for (mut phases, mut creature, mut sprite, ranged_attack, mut separation) in
+        boss_query.iter_mut()
+    {
+        if phases.enraged {
+            // Pulse red for the rest of the fight
+            let pulse = (time.elapsed_seconds() * 6.0).sin() * 0.5 + 0.5;
+            sprite.color = Color::rgb(1.0, 0.4 + pulse * 0.6, 0.4 + pulse * 0.6);
+            continue;
+        }
+
+        phases.enrage_timer.tick(time.delta());
+        if phases.enrage_timer.finished() {
+            phases.enraged = true;
+            scale_speed(
+                &mut creature,
+                separation.as_deref_mut(),
+                ENRAGE_SPEED_MULTIPLIER,
+            );
+            if let Some(mut ranged_attack) = ranged_attack {
+                enrage_cooldown(&mut ranged_attack);
+            }
+        }
+    }
+}
+
+// A phase change might get cut short by the player dying mid pause
+pub fn boss_phase_cleanup(mut time: ResMut<Time>) {
+    time.set_relative_speed(1.0);
+}
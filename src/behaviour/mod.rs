@@ -1,11 +1,17 @@
 use bevy::prelude::*;
 use seldom_state::prelude::*;
 
-use self::{states::BehaviourStatesPlugin, triggers::BehaviourTriggerPlugin};
+use self::{
+    boss_phase::BossPhasePlugin, navigation::NavigationPlugin, separation::SeparationPlugin,
+    states::BehaviourStatesPlugin, target::TargetPlugin, triggers::BehaviourTriggerPlugin,
+};
 
 // pub mod approach_and_keep_distance;
+pub mod boss_phase;
+pub mod navigation;
 pub mod separation;
 pub mod states;
+pub mod target;
 pub mod triggers;
 
 pub struct BehaviourPlugin;
@@ -14,6 +20,10 @@ impl Plugin for BehaviourPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(StateMachinePlugin)
             .add_plugin(BehaviourTriggerPlugin)
-            .add_plugin(BehaviourStatesPlugin);
+            .add_plugin(BehaviourStatesPlugin)
+            .add_plugin(NavigationPlugin)
+            .add_plugin(SeparationPlugin)
+            .add_plugin(TargetPlugin)
+            .add_plugin(BossPhasePlugin);
     }
 }
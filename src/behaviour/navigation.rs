@@ -0,0 +1,229 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LdtkLevel;
+use bevy_rapier2d::prelude::{QueryFilter, RapierContext};
+
+use crate::{
+    app_state::AppState,
+    entity::player::Player,
+    game::{level_manager::Wall, GameState},
+};
+
+// IntGrid values of the `NonPassable` and `BreakableWall` cells
+const WALL_VALUE: i32 = 1;
+const BREAKABLE_WALL_VALUE: i32 = 2;
+
+// Path costs, diagonals are roughly sqrt(2) times a straight step
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>()
+            .add_system(build_nav_grid.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                update_flow_field
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(build_nav_grid),
+            );
+    }
+}
+
+// Walkable cells of the current level, plus a flow field leading every cell to the player.
+// Enemies chasing the player all share the one flow field instead of pathfinding on their own
+#[derive(Resource, Default)]
+pub struct NavGrid {
+    pub origin: Vec2,
+    pub cell_size: f32,
+    pub width: i32,
+    pub height: i32,
+    // Rows go bottom to top, like world coordinates
+    pub walkable: Vec<bool>,
+    flow_target: Option<Entity>,
+    flow_origin: Option<IVec2>,
+    // Path cost from each cell to the flow target, u32::MAX when unreachable
+    distances: Vec<u32>,
+}
+
+impl NavGrid {
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        (cell.x >= 0 && cell.y >= 0 && cell.x < self.width && cell.y < self.height)
+            .then(|| (cell.y * self.width + cell.x) as usize)
+    }
+
+    pub fn cell(&self, position: Vec2) -> Option<IVec2> {
+        if self.cell_size <= 0.0 {
+            return None;
+        }
+        let cell = ((position - self.origin) / self.cell_size)
+            .floor()
+            .as_ivec2();
+        self.index(cell).map(|_| cell)
+    }
+
+    pub fn cell_center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + Vec2::splat(0.5)) * self.cell_size
+    }
+
+    // Opens up the cell at the given position, e.g. once the wall in it is broken
+    pub fn open_cell(&mut self, position: Vec2) {
+        if let Some(index) = self.cell(position).and_then(|cell| self.index(cell)) {
+            self.walkable[index] = true;
+            // There might be a shorter path through it now
+            self.flow_origin = None;
+        }
+    }
+
+    pub fn is_walkable(&self, cell: IVec2) -> bool {
+        self.index(cell)
+            .map(|index| self.walkable[index])
+            .unwrap_or(false)
+    }
+
+    // Walkable neighbours in all 8 directions, diagonals can't cut wall corners
+    fn neighbours(&self, cell: IVec2) -> impl Iterator<Item = (IVec2, u32)> + '_ {
+        (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |y| IVec2::new(x, y)))
+            .filter(|offset| *offset != IVec2::ZERO)
+            .filter_map(move |offset| {
+                let neighbour = cell + offset;
+                if !self.is_walkable(neighbour) {
+                    return None;
+                }
+                if offset.x != 0 && offset.y != 0 {
+                    let corners_clear = self.is_walkable(cell + IVec2::new(offset.x, 0))
+                        && self.is_walkable(cell + IVec2::new(0, offset.y));
+                    return corners_clear.then_some((neighbour, DIAGONAL_COST));
+                }
+                Some((neighbour, STRAIGHT_COST))
+            })
+    }
+
+    // Dijkstra outwards from the target's cell
+    fn build_flow_field(&mut self, target: Entity, origin: IVec2) {
+        self.flow_target = Some(target);
+        self.flow_origin = Some(origin);
+        self.distances = vec![u32::MAX; self.walkable.len()];
+
+        let origin_index = match self.index(origin) {
+            Some(index) => index,
+            None => return,
+        };
+        self.distances[origin_index] = 0;
+
+        let mut open = BinaryHeap::new();
+        open.push(Reverse((0, origin.x, origin.y)));
+        while let Some(Reverse((distance, x, y))) = open.pop() {
+            let cell = IVec2::new(x, y);
+            if distance > self.distances[self.index(cell).unwrap()] {
+                continue;
+            }
+            let neighbours: Vec<(IVec2, u32)> = self.neighbours(cell).collect();
+            for (neighbour, cost) in neighbours {
+                let index = self.index(neighbour).unwrap();
+                let neighbour_distance = distance + cost;
+                if neighbour_distance < self.distances[index] {
+                    self.distances[index] = neighbour_distance;
+                    open.push(Reverse((neighbour_distance, neighbour.x, neighbour.y)));
+                }
+            }
+        }
+    }
+
+    // Direction to step in from the given position to get closer to the flow field's target,
+    // None if the flow field is for something else or there's no path
+    pub fn flow_direction(&self, target: Entity, position: Vec2) -> Option<Vec2> {
+        if self.flow_target != Some(target) {
+            return None;
+        }
+        let cell = self.cell(position)?;
+        let distance = self.distances.get(self.index(cell)?).copied()?;
+
+        let (next_cell, next_distance) = self
+            .neighbours(cell)
+            .map(|(neighbour, _)| (neighbour, self.distances[self.index(neighbour).unwrap()]))
+            .min_by_key(|(_, distance)| *distance)?;
+        if next_distance == u32::MAX || next_distance >= distance {
+            return None;
+        }
+        Some((self.cell_center(next_cell) - position).normalize_or_zero())
+    }
+}
+
+// Whether a straight line between the two points is clear of walls
+pub fn line_of_sight(
+    rapier_context: &RapierContext,
+    walls: &Query<(), With<Wall>>,
+    from: Vec2,
+    to: Vec2,
+) -> bool {
+    let is_wall = |collider: Entity| walls.get(collider).is_ok();
+    let filter = QueryFilter::new().exclude_sensors().predicate(&is_wall);
+    rapier_context
+        .cast_ray(from, to - from, 1.0, true, filter)
+        .is_none()
+}
+
+// Rebuild the grid whenever a level is spawned or moved into place
+pub fn build_nav_grid(
+    mut nav_grid: ResMut<NavGrid>,
+    level_query: Query<(&Transform, &Handle<LdtkLevel>), Changed<Transform>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+) {
+    for (transform, level_handle) in level_query.iter() {
+        let ldtk_level = match ldtk_levels.get(level_handle) {
+            Some(ldtk_level) => ldtk_level,
+            None => continue,
+        };
+        let layer = match ldtk_level
+            .level
+            .layer_instances
+            .iter()
+            .flatten()
+            .find(|layer| !layer.int_grid_csv.is_empty())
+        {
+            Some(layer) => layer,
+            None => continue,
+        };
+
+        // LDtk rows go top to bottom, flip them to match the world
+        let mut walkable = vec![true; (layer.c_wid * layer.c_hei) as usize];
+        for (i, value) in layer.int_grid_csv.iter().enumerate() {
+            let x = i as i32 % layer.c_wid;
+            let y = layer.c_hei - 1 - i as i32 / layer.c_wid;
+            walkable[(y * layer.c_wid + x) as usize] =
+                *value != WALL_VALUE && *value != BREAKABLE_WALL_VALUE;
+        }
+
+        *nav_grid = NavGrid {
+            origin: transform.translation.truncate(),
+            cell_size: layer.grid_size as f32,
+            width: layer.c_wid,
+            height: layer.c_hei,
+            walkable,
+            ..default()
+        };
+    }
+}
+
+// Only needs redoing when the player moves to another cell
+pub fn update_flow_field(
+    mut nav_grid: ResMut<NavGrid>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+) {
+    let (player, transform) = match player_query.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let cell = match nav_grid.cell(transform.translation.truncate()) {
+        Some(cell) => cell,
+        None => return,
+    };
+    if nav_grid.flow_target != Some(player) || nav_grid.flow_origin != Some(cell) {
+        nav_grid.build_flow_field(player, cell);
+    }
+}
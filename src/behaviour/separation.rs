@@ -1,4 +1,10 @@
-use bevy::prelude::*;
+use std::time::Instant;
+
+use bevy::{
+    diagnostic::{DiagnosticId, Diagnostics},
+    prelude::*,
+    utils::HashMap,
+};
 use rand::Rng;
 
 use crate::{
@@ -8,6 +14,21 @@ use crate::{
 
 const EPSILON: f32 = 1e-6;
 
+// How long the separation system takes each frame, in milliseconds. Only logged if the diagnostic
+// is registered, see `DebugPlugin`
+pub const SEPARATION_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x5e9a_7a71_0000_4c1a_9b3e_2d4f_6a8b_0c1d);
+
+pub struct SeparationPlugin;
+
+impl Plugin for SeparationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SpatialHash::new(PIXELS_PER_METER * 2.0))
+            .add_system(update_spatial_hash)
+            .add_system(separation_system.after(update_spatial_hash));
+    }
+}
+
 #[derive(Component, Reflect)]
 pub struct Separation {
     pub radius: f32,
@@ -61,30 +82,83 @@ fn separate(
     Some((steer, steer.length()))
 }
 
-// TODO: This is O(n^2), look into using a quadtree or something similar. Performance isn't an issue until there are a lot of enemies on screen.
-pub fn separation_system<T: Component>(
-    mut enemy_query: Query<(&Separation, &Transform, &mut Velocity, &T, &mut Creature)>,
+// Buckets every entity with `Separation` by position, so finding neighbours only has to look at
+// the surrounding cells instead of every other enemy
+#[derive(Resource)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((entity, position));
+    }
+
+    // Everything within the radius of the position, including whatever is at the position itself
+    pub fn within(&self, position: Vec2, radius: f32) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let min = self.cell(position - Vec2::splat(radius));
+        let max = self.cell(position + Vec2::splat(radius));
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, other_position)| position.distance(*other_position) < radius)
+    }
+}
+
+// Rebuilt from scratch every frame, it's cheap compared to checking every pair
+pub fn update_spatial_hash(
+    mut spatial_hash: ResMut<SpatialHash>,
+    query: Query<(Entity, &Transform), With<Separation>>,
 ) {
-    // Create a vector of positions for the enemy entities.
-    let mut positions: Vec<Vec2> = Vec::new();
-    for (_, transform, _, _, _) in enemy_query.iter_mut() {
-        positions.push(transform.translation.truncate());
+    spatial_hash.clear();
+    for (entity, transform) in query.iter() {
+        spatial_hash.insert(entity, transform.translation.truncate());
     }
+}
+
+// Handles every species at once, so different enemy types also keep out of each other's way
+pub fn separation_system(
+    spatial_hash: Res<SpatialHash>,
+    mut diagnostics: ResMut<Diagnostics>,
+    mut enemy_query: Query<(
+        Entity,
+        &Separation,
+        &Transform,
+        &mut Velocity,
+        &mut Creature,
+    )>,
+) {
+    let start = Instant::now();
 
-    // Loop through the query and calculate the separation for each enemy entity.
     // Adjust the Velocity for each entity to steer away from nearby enemies.
-    for (separation, transform, mut velocity, _, mut creature) in enemy_query.iter_mut() {
+    for (entity, separation, transform, mut velocity, mut creature) in enemy_query.iter_mut() {
         // Get the position of the current enemy entity.
         let enemy_position = transform.translation.truncate();
 
-        // Filter and map the positions vector to get the positions of nearby enemies.
-        let nearby_positions: Vec<Vec2> = positions
-            .iter()
-            .filter(|&other_position| {
-                *other_position != enemy_position
-                    && enemy_position.distance(*other_position) < separation.radius
-            })
-            .copied()
+        // Get the positions of nearby enemies from the spatial hash.
+        let nearby_positions: Vec<Vec2> = spatial_hash
+            .within(enemy_position, separation.radius)
+            .filter(|(other, other_position)| *other != entity && *other_position != enemy_position)
+            .map(|(_, other_position)| other_position)
             .collect();
 
         // Calculate the separation for the current enemy entity and adjust its Velocity accordingly.
@@ -118,4 +192,6 @@ pub fn separation_system<T: Component>(
             velocity.value += separation_scaled * separation.separation_strength;
         }
     }
+
+    diagnostics.add_measurement(SEPARATION_TIME, || start.elapsed().as_secs_f64() * 1000.0);
 }
@@ -1,14 +1,22 @@
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierContext;
 use rand::Rng;
 
-use crate::entity::creature::{Creature, Knockback, Velocity};
+use crate::{
+    behaviour::{
+        navigation::{line_of_sight, NavGrid},
+        target::{TargetResolver, TargetRole},
+    },
+    entity::creature::{Creature, Knockback, Velocity},
+    game::level_manager::Wall,
+};
 
 // Entities in the `ApproachAndKeepDistance` state should move towards the given entity if they are
 // too far away, and move away if they are too close
 #[derive(Clone, Component, Reflect)]
 #[component(storage = "SparseSet")]
 pub struct ApproachAndKeepDistance {
-    pub target: Entity,
+    pub target: TargetRole,
     pub inner_distance: f32,
     pub outer_distance: f32,
 }
@@ -23,24 +31,37 @@ pub fn approach_and_keep_distance(
         Option<&Knockback>,
     )>,
     time: Res<Time>,
+    nav_grid: Res<NavGrid>,
+    rapier_context: Res<RapierContext>,
+    walls: Query<(), With<Wall>>,
+    resolver: Res<TargetResolver>,
 ) {
     let mut rng = rand::thread_rng();
 
     for (entity, mut velocity, creature, approach, knockback) in approach_query.iter_mut() {
         {
-            let target_position = transforms
-                .get(approach.target)
-                .unwrap_or(&Transform::from_translation(Vec3::ZERO))
-                .translation
-                .truncate();
-            let follower_position = transforms.get(entity).unwrap().translation.truncate();
+            // Nothing to approach, e.g. the player has been despawned
+            let (follower_position, target_position) =
+                match resolver.resolve_positions(approach.target, entity, &transforms) {
+                    Some(positions) => positions,
+                    None => continue,
+                };
 
             let distance_from_player = target_position.distance(follower_position);
 
             let change_velocity;
             // move to outer_distance
             if distance_from_player > approach.outer_distance {
-                let direction = target_position - follower_position;
+                // Walk around walls instead of into them when the target is out of sight
+                let direction =
+                    if line_of_sight(&rapier_context, &walls, follower_position, target_position) {
+                        target_position - follower_position
+                    } else {
+                        resolver
+                            .resolve(approach.target, entity, follower_position)
+                            .and_then(|target| nav_grid.flow_direction(target, follower_position))
+                            .unwrap_or(target_position - follower_position)
+                    };
                 let acceleration = creature.acceleration * time.delta_seconds();
                 change_velocity = direction.normalize_or_zero() * acceleration;
             }
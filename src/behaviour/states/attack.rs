@@ -1,14 +1,19 @@
 use bevy::prelude::*;
-use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, Sensor};
 
 use crate::{
     animation::Animated,
-    entity::{creature::Lifetime, lab_boss::LabBoss, EnemyHurtbox, EnemyHurtboxDamage},
+    entity::{
+        lab_boss::LabBoss,
+        telegraph::{spawn_telegraph, TelegraphShape, TelegraphedAttack},
+    },
     PIXELS_PER_METER,
 };
 
 use super::approach_and_keep_distance::ApproachAndKeepDistance;
 
+// How long the slam is telegraphed for
+const LAB_BOSS_WIND_UP: f32 = 0.6;
+
 // Entities in the `ApproachAndKeepDistance` state should move towards the given entity if they are
 // too far away, and move away if they are too close
 #[derive(Clone, Component, Reflect)]
@@ -24,34 +29,30 @@ pub struct AttackAndKeepDistance {
 
 pub fn lab_boss_attack(
     mut commands: Commands,
-    mut projectile_query: Query<(
-        Entity,
-        &LabBossAttack,
-        &mut LabBoss,
-        &mut Animated,
-        &TextureAtlasSprite,
-    )>,
+    time: Res<Time>,
+    mut attack_query: Query<(Entity, &mut LabBoss, &mut Animated), With<LabBossAttack>>,
 ) {
-    for (entity, _, lab_boss, mut animated, sprite) in projectile_query.iter_mut() {
-        // If the attack cooldown is finished, and the attack button is just pressed, and the player is not rolling, start attacking
+    for (entity, mut lab_boss, mut animated) in attack_query.iter_mut() {
         animated.first = 1;
         animated.last = 2;
-        if (sprite.index) == 2 {
-            commands.entity(entity).with_children(|parent| {
-                parent.spawn((
-                    EnemyHurtbox {
-                        collider: Collider::cuboid(PIXELS_PER_METER * 2.0, PIXELS_PER_METER * 1.0),
-                        damage: EnemyHurtboxDamage(14),
-                        sensor: Sensor,
-                        transform: Transform::from_xyz(0.0, -PIXELS_PER_METER * 1.0, 0.0),
-                        ..default()
-                    },
-                    ActiveCollisionTypes::STATIC_STATIC,
-                    Lifetime {
-                        timer: lab_boss.attack_timer.clone(),
-                    },
-                ));
-            });
+
+        // Slam the ground in front of it, after showing where
+        if lab_boss.attack_cooldown.tick(time.delta()).finished() {
+            spawn_telegraph(
+                &mut commands,
+                entity,
+                Vec2::new(0.0, -PIXELS_PER_METER * 1.0),
+                Vec2::X,
+                LAB_BOSS_WIND_UP,
+                TelegraphShape::Rectangle {
+                    half_extents: Vec2::new(PIXELS_PER_METER * 2.0, PIXELS_PER_METER * 1.0),
+                },
+                TelegraphedAttack::Hurtbox {
+                    damage: 14,
+                    lifetime: 0.2,
+                },
+            );
+            lab_boss.attack_cooldown.reset();
         }
     }
 }
@@ -0,0 +1,116 @@
+use bevy::prelude::*;
+
+use crate::{
+    behaviour::target::{TargetResolver, TargetRole},
+    entity::creature::{ChangeColor, Velocity, Vulnerable},
+};
+
+// Entities in the `Dash` state wind up in place, then charge in a straight line at where the target
+// was when the wind up ended, then stand still for a moment while they recover
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct Dash {
+    pub target: TargetRole,
+    pub speed: f32,
+    pub wind_up: Timer,
+    pub dash: Timer,
+    pub recovery: Timer,
+    pub direction: Vec2,
+}
+
+impl Dash {
+    pub fn new(target: TargetRole, speed: f32) -> Self {
+        Self {
+            target,
+            speed,
+            wind_up: Timer::from_seconds(0.6, TimerMode::Once),
+            dash: Timer::from_seconds(0.4, TimerMode::Once),
+            recovery: Timer::from_seconds(1.0, TimerMode::Once),
+            direction: Vec2::ZERO,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.recovery.finished()
+    }
+}
+
+// How long before an entity can dash again, ticks outside of the `Dash` state
+#[derive(Component, Reflect)]
+pub struct DashCooldown {
+    pub timer: Timer,
+}
+
+impl DashCooldown {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+pub fn dash_cooldown_system(time: Res<Time>, mut query: Query<&mut DashCooldown>) {
+    for mut cooldown in query.iter_mut() {
+        cooldown.timer.tick(time.delta());
+    }
+}
+
+pub fn dash(
+    mut commands: Commands,
+    transforms: Query<&Transform>,
+    mut dash_query: Query<(Entity, &mut Dash, &mut Velocity, Option<&mut DashCooldown>)>,
+    time: Res<Time>,
+    resolver: Res<TargetResolver>,
+) {
+    for (entity, mut dash, mut velocity, cooldown) in dash_query.iter_mut() {
+        // Telegraph the dash by flashing while standing still
+        if !dash.wind_up.finished() {
+            dash.wind_up.tick(time.delta());
+            velocity.value = Vec2::ZERO;
+            if dash.wind_up.elapsed_secs() % 0.2 < 0.1 {
+                commands.entity(entity).insert(ChangeColor {
+                    color: Color::ORANGE,
+                    timer: Timer::from_seconds(0.05, TimerMode::Once),
+                });
+            }
+
+            // Commit to a direction once the wind up is over
+            if dash.wind_up.just_finished() {
+                // With no target the dash just fizzles out in place
+                if let Some((position, target_position)) =
+                    resolver.resolve_positions(dash.target, entity, &transforms)
+                {
+                    dash.direction = (target_position - position).normalize_or_zero();
+                }
+            }
+        } else if !dash.dash.finished() {
+            dash.dash.tick(time.delta());
+            velocity.value = dash.direction * dash.speed;
+
+            if dash.dash.just_finished() {
+                commands.entity(entity).insert(Vulnerable {
+                    damage_multiplier: 1.5,
+                });
+            }
+        } else if !dash.recovery.finished() {
+            dash.recovery.tick(time.delta());
+            velocity.value = Vec2::ZERO;
+
+            if dash.recovery.just_finished() {
+                commands.entity(entity).remove::<Vulnerable>();
+                if let Some(mut cooldown) = cooldown {
+                    cooldown.timer.reset();
+                }
+            }
+        }
+    }
+}
+
+// Don't leave the entity vulnerable if it gets pulled out of the dash early
+pub fn dash_exit(mut commands: Commands, mut removals: RemovedComponents<Dash>) {
+    for entity in removals.iter() {
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.remove::<Vulnerable>();
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierContext;
+
+use crate::{
+    behaviour::{
+        navigation::{line_of_sight, NavGrid},
+        target::{TargetResolver, TargetRole},
+    },
+    entity::creature::{Creature, Knockback, Velocity},
+    game::level_manager::Wall,
+};
+
+// Entities in the `Follow` state should stay within the given distance of the target, e.g. skullers
+// trailing after their adept. They lose track of it past `range`, see `LostFollowTarget`
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct Follow {
+    pub target: TargetRole,
+    pub distance: f32,
+    pub range: f32,
+}
+
+pub fn follow(
+    transforms: Query<&Transform>,
+    mut follow_query: Query<(
+        Entity,
+        &mut Velocity,
+        &Creature,
+        &Follow,
+        Option<&Knockback>,
+    )>,
+    time: Res<Time>,
+    nav_grid: Res<NavGrid>,
+    rapier_context: Res<RapierContext>,
+    walls: Query<(), With<Wall>>,
+    resolver: Res<TargetResolver>,
+) {
+    for (entity, mut velocity, creature, follow, knockback) in follow_query.iter_mut() {
+        // Nothing to follow, e.g. the leader has been killed
+        let (follower_position, target_position) =
+            match resolver.resolve_positions(follow.target, entity, &transforms) {
+                Some(positions) => positions,
+                None => continue,
+            };
+
+        // Close enough, let friction slow us down
+        if follower_position.distance(target_position) <= follow.distance {
+            continue;
+        }
+
+        let direction =
+            if line_of_sight(&rapier_context, &walls, follower_position, target_position) {
+                target_position - follower_position
+            } else {
+                resolver
+                    .resolve(follow.target, entity, follower_position)
+                    .and_then(|target| nav_grid.flow_direction(target, follower_position))
+                    .unwrap_or(target_position - follower_position)
+            };
+        let mut new_velocity = velocity.value
+            + direction.normalize_or_zero() * creature.acceleration * time.delta_seconds();
+
+        // apply max_speed
+        let speed = new_velocity.length();
+        if knockback.is_none() && speed > creature.max_speed {
+            new_velocity *= creature.max_speed / speed;
+        }
+
+        velocity.value = new_velocity;
+    }
+}
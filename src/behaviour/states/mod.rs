@@ -1,17 +1,23 @@
 use bevy::prelude::*;
 
-use crate::app_state::AppState;
+use crate::{app_state::AppState, game::GameState};
 
 use self::approach_and_keep_distance::approach_and_keep_distance;
 use self::attack::lab_boss_attack;
 use self::attack::lab_boss_stop_attack;
+use self::dash::{dash, dash_cooldown_system, dash_exit};
 use self::fire_projectile::fire_projectile;
+use self::follow::follow;
+use self::phase_transition::phase_transition;
 use self::wander::wander;
 
 pub mod approach_and_keep_distance;
 pub mod attack;
+pub mod dash;
 pub mod fire_projectile;
+pub mod follow;
 pub mod idle;
+pub mod phase_transition;
 pub mod wander;
 
 pub struct BehaviourStatesPlugin;
@@ -20,8 +26,13 @@ impl Plugin for BehaviourStatesPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(approach_and_keep_distance)
             .add_system(wander)
+            .add_system(follow)
             .add_system(fire_projectile.in_set(OnUpdate(AppState::InGame)))
             .add_system(lab_boss_attack.in_set(OnUpdate(AppState::InGame)))
-            .add_system(lab_boss_stop_attack);
+            .add_system(lab_boss_stop_attack)
+            .add_system(dash.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(dash_cooldown_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(dash_exit)
+            .add_system(phase_transition.in_set(OnUpdate(GameState::InLevel)));
     }
 }
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use seldom_state::prelude::StateMachine;
+
+use crate::{
+    behaviour::{boss_phase::BossPhases, separation::Separation},
+    entity::creature::{Creature, Invulnerable, Velocity},
+};
+
+// How long the phase change lasts, in real time since the game is slowed down meanwhile
+const PHASE_TRANSITION_SECONDS: f32 = 1.5;
+const PHASE_TRANSITION_TIME_SPEED: f32 = 0.25;
+
+// Bosses in the `PhaseTransition` state stand still and can't be hurt while everything else slows
+// down, then they swap over to the next phase's state machine
+#[derive(Clone, Component, Reflect)]
+#[component(storage = "SparseSet")]
+pub struct PhaseTransition {
+    pub timer: Timer,
+}
+
+impl Default for PhaseTransition {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(PHASE_TRANSITION_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+pub fn phase_transition(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    mut boss_query: Query<(
+        Entity,
+        &mut PhaseTransition,
+        &mut BossPhases,
+        &mut Creature,
+        &mut Velocity,
+        &mut TextureAtlasSprite,
+        Option<&mut Separation>,
+    )>,
+) {
+    for (
+        entity,
+        mut transition,
+        mut phases,
+        mut creature,
+        mut velocity,
+        mut sprite,
+        mut separation,
+    ) in boss_query.iter_mut()
+    {
+        if transition.timer.elapsed_secs() == 0.0 {
+            commands.entity(entity).insert(Invulnerable);
+            time.set_relative_speed(PHASE_TRANSITION_TIME_SPEED);
+        }
+
+        velocity.value = Vec2::ZERO;
+        transition.timer.tick(time.raw_delta());
+
+        // Flash white and red while powering up
+        sprite.color = if (transition.timer.elapsed_secs() * 10.0) as u32 % 2 == 0 {
+            Color::WHITE
+        } else {
+            Color::rgb(1.0, 0.3, 0.3)
+        };
+
+        if transition.timer.finished() {
+            time.set_relative_speed(1.0);
+            sprite.color = Color::WHITE;
+
+            let mut entity_commands = commands.entity(entity);
+            entity_commands.remove::<(PhaseTransition, Invulnerable)>();
+            let fraction = creature.health / creature.max_health;
+            match phases.next_phase(fraction) {
+                Some(phase) => phases.advance(
+                    &mut entity_commands,
+                    &mut creature,
+                    separation.as_deref_mut(),
+                    phase,
+                ),
+                // Shouldn't happen, but don't leave the boss stuck in the transition
+                None => {
+                    entity_commands.remove::<StateMachine>();
+                    phases.insert_phase(&mut entity_commands, phases.current());
+                }
+            }
+        }
+    }
+}
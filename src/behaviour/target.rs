@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+
+use crate::entity::{faction::Faction, player::Player, Enemy};
+
+pub struct TargetPlugin;
+
+impl Plugin for TargetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TargetResolver>()
+            .add_system(update_target_resolver.in_base_set(CoreSet::PreUpdate));
+    }
+}
+
+// Who a state or trigger is aimed at. States and triggers store the role rather than an `Entity`,
+// and the resolver turns it into whoever currently fills that role
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, FromReflect)]
+pub enum TargetRole {
+    #[default]
+    Player,
+    NearestAlly,
+    Leader,
+    // Same candidates as `NearestAlly`, for things on the player's side like their projectiles
+    NearestEnemy,
+}
+
+// Marker component for enemies others can rally around, see `Follow`
+#[derive(Component, Reflect, Default)]
+pub struct Leader;
+
+// Rebuilt at the start of every frame, so a despawned player just resolves to nothing and a
+// recreated one is picked up automatically
+#[derive(Resource, Default)]
+pub struct TargetResolver {
+    player: Option<Entity>,
+    allies: Vec<(Entity, Vec2)>,
+    leaders: Vec<(Entity, Vec2)>,
+    // Enemies on the player's side, like charmed ones and friendly summons
+    friendly: Vec<Entity>,
+}
+
+impl TargetResolver {
+    pub fn player(&self) -> Option<Entity> {
+        self.player
+    }
+
+    // The entity filling the role for the given entity, if there is one
+    pub fn resolve(&self, role: TargetRole, entity: Entity, position: Vec2) -> Option<Entity> {
+        let nearest = |candidates: &[(Entity, Vec2)]| {
+            candidates
+                .iter()
+                .filter(|(candidate, _)| *candidate != entity)
+                .min_by(|(_, a), (_, b)| {
+                    a.distance_squared(position)
+                        .total_cmp(&b.distance_squared(position))
+                })
+                .map(|(candidate, _)| *candidate)
+        };
+
+        match role {
+            // Whatever's hunting the player goes after the other enemies instead when it's friendly
+            TargetRole::Player if self.friendly.contains(&entity) => nearest(&self.allies),
+            TargetRole::Player => self.player,
+            TargetRole::NearestAlly | TargetRole::NearestEnemy => nearest(&self.allies),
+            TargetRole::Leader => nearest(&self.leaders),
+        }
+    }
+
+    // Resolves the role and returns the positions of the entity and its target
+    pub fn resolve_positions(
+        &self,
+        role: TargetRole,
+        entity: Entity,
+        transforms: &Query<&Transform>,
+    ) -> Option<(Vec2, Vec2)> {
+        let position = transforms.get(entity).ok()?.translation.truncate();
+        let target = self.resolve(role, entity, position)?;
+        let target_position = transforms.get(target).ok()?.translation.truncate();
+        Some((position, target_position))
+    }
+}
+
+pub fn update_target_resolver(
+    mut resolver: ResMut<TargetResolver>,
+    player_query: Query<Entity, With<Player>>,
+    ally_query: Query<(Entity, &Transform, Option<&Leader>, Option<&Faction>), With<Enemy>>,
+) {
+    resolver.player = player_query.iter().next();
+
+    resolver.allies.clear();
+    resolver.leaders.clear();
+    resolver.friendly.clear();
+    for (entity, transform, leader, faction) in ally_query.iter() {
+        if faction == Some(&Faction::Player) {
+            resolver.friendly.push(entity);
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        resolver.allies.push((entity, position));
+        if leader.is_some() {
+            resolver.leaders.push((entity, position));
+        }
+    }
+}
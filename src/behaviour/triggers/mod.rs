@@ -1,12 +1,32 @@
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierContext;
 
 use seldom_state::prelude::*;
 
+use crate::{entity::creature::Creature, game::level_manager::Wall};
+
+use super::{
+    boss_phase::BossPhases,
+    navigation::line_of_sight,
+    states::{
+        dash::{Dash, DashCooldown},
+        follow::Follow,
+        phase_transition::PhaseTransition,
+    },
+    target::{TargetResolver, TargetRole},
+};
+
 pub struct BehaviourTriggerPlugin;
 
 impl Plugin for BehaviourTriggerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(TriggerPlugin::<Near>::default());
+        app.add_plugin(TriggerPlugin::<Near>::default())
+            .add_plugin(TriggerPlugin::<CanDash>::default())
+            .add_plugin(TriggerPlugin::<DashFinished>::default())
+            .add_plugin(TriggerPlugin::<LineOfSight>::default())
+            .add_plugin(TriggerPlugin::<LostFollowTarget>::default())
+            .add_plugin(TriggerPlugin::<PhaseThreshold>::default())
+            .add_plugin(TriggerPlugin::<AndTrigger<NotTrigger<Near>, LineOfSight>>::default());
     }
 }
 
@@ -14,7 +34,7 @@ impl Plugin for BehaviourTriggerPlugin {
 // Example: https://github.com/Seldom-SE/seldom_state/blob/main/examples/chase.rs
 #[derive(Clone, Copy, Reflect)]
 pub struct Near {
-    pub target: Entity,
+    pub target: TargetRole,
     pub range: f32,
 }
 
@@ -25,8 +45,7 @@ impl Trigger for Near {
     // `type Param<'w, 's> = (SQuery<&'static Transform>, SRes<Time>);`
     // Triggers are immutable; you may not access system params mutably
     // Do not query for the `StateMachine` component in this type. This, unfortunately, will panic.
-    // `Time` is included here to demonstrate how to get multiple system params
-    type Param<'w, 's> = (Query<'w, 's, &'static Transform>, Res<'w, Time>);
+    type Param<'w, 's> = (Query<'w, 's, &'static Transform>, Res<'w, TargetResolver>);
     // These types are used by transition builders, for dataflow from triggers to transitions
     // See `StateMachine::trans_builder`
     type Ok = f32;
@@ -39,15 +58,173 @@ impl Trigger for Near {
     fn trigger(
         &self,
         entity: Entity,
-        (transforms, _time): &Self::Param<'_, '_>,
+        (transforms, resolver): &Self::Param<'_, '_>,
     ) -> Result<f32, f32> {
-        // Find the displacement between the target and this entity
-        let delta = transforms.get(self.target).unwrap().translation.truncate()
-            - transforms.get(entity).unwrap().translation.truncate();
+        // Nobody to be near, e.g. the player has been despawned
+        let (position, target_position) = resolver
+            .resolve_positions(self.target, entity, transforms)
+            .ok_or(f32::INFINITY)?;
 
-        // Use the Pythagorean Theorem to determine whether the target is within range
-        // If it is, return `Ok` to trigger!
-        let distance = (delta.x * delta.x + delta.y * delta.y).sqrt();
+        // If the target is within range, return `Ok` to trigger!
+        let distance = position.distance(target_position);
         (distance <= self.range).then_some(distance).ok_or(distance)
     }
 }
+
+// Like `Near`, but also waits for the entity's `DashCooldown` to finish
+#[derive(Clone, Copy, Reflect)]
+pub struct CanDash {
+    pub target: TargetRole,
+    pub range: f32,
+}
+
+impl Trigger for CanDash {
+    type Param<'w, 's> = (
+        Query<'w, 's, &'static Transform>,
+        Query<'w, 's, &'static DashCooldown>,
+        Res<'w, TargetResolver>,
+    );
+    type Ok = f32;
+    type Err = f32;
+
+    fn trigger(
+        &self,
+        entity: Entity,
+        (transforms, cooldowns, resolver): &Self::Param<'_, '_>,
+    ) -> Result<f32, f32> {
+        let (position, target_position) = resolver
+            .resolve_positions(self.target, entity, transforms)
+            .ok_or(f32::INFINITY)?;
+        let distance = position.distance(target_position);
+        let ready = cooldowns
+            .get(entity)
+            .map(|cooldown| cooldown.timer.finished())
+            .unwrap_or(true);
+
+        (ready && distance <= self.range)
+            .then_some(distance)
+            .ok_or(distance)
+    }
+}
+
+// Triggers once the entity's dash has played out, wind up, dash and recovery
+#[derive(Clone, Copy, Reflect)]
+pub struct DashFinished;
+
+impl Trigger for DashFinished {
+    type Param<'w, 's> = Query<'w, 's, &'static Dash>;
+    type Ok = ();
+    type Err = ();
+
+    fn trigger(&self, entity: Entity, dashes: &Self::Param<'_, '_>) -> Result<(), ()> {
+        match dashes.get(entity) {
+            Ok(dash) if dash.finished() => Ok(()),
+            _ => Err(()),
+        }
+    }
+}
+
+// Triggers when the target is within range and no wall is in the way
+#[derive(Clone, Copy, Reflect)]
+pub struct LineOfSight {
+    pub target: TargetRole,
+    pub range: f32,
+}
+
+impl Trigger for LineOfSight {
+    type Param<'w, 's> = (
+        Query<'w, 's, &'static Transform>,
+        Query<'w, 's, (), With<Wall>>,
+        Res<'w, RapierContext>,
+        Res<'w, TargetResolver>,
+    );
+    type Ok = f32;
+    type Err = f32;
+
+    fn trigger(
+        &self,
+        entity: Entity,
+        (transforms, walls, rapier_context, resolver): &Self::Param<'_, '_>,
+    ) -> Result<f32, f32> {
+        let (origin, target) = resolver
+            .resolve_positions(self.target, entity, transforms)
+            .ok_or(f32::INFINITY)?;
+        let delta = target - origin;
+        let distance = delta.length();
+        if distance > self.range {
+            return Err(distance);
+        }
+
+        if line_of_sight(rapier_context, walls, origin, target) {
+            Ok(distance)
+        } else {
+            Err(distance)
+        }
+    }
+}
+
+// Triggers when whatever the entity is following is gone or out of its `Follow::range`. One
+// transition out of `Follow` works for every target it's given
+#[derive(Clone, Copy, Reflect)]
+pub struct LostFollowTarget;
+
+impl Trigger for LostFollowTarget {
+    type Param<'w, 's> = (
+        Query<'w, 's, &'static Transform>,
+        Query<'w, 's, &'static Follow>,
+        Res<'w, TargetResolver>,
+    );
+    type Ok = ();
+    type Err = ();
+
+    fn trigger(
+        &self,
+        entity: Entity,
+        (transforms, follows, resolver): &Self::Param<'_, '_>,
+    ) -> Result<(), ()> {
+        let follow = follows.get(entity).map_err(|_| ())?;
+        match resolver.resolve_positions(follow.target, entity, transforms) {
+            Some((position, target_position))
+                if position.distance(target_position) <= follow.range =>
+            {
+                Err(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Triggers when both triggers do, e.g. `AndTrigger(Near { .. }, LineOfSight { .. })`
+#[derive(Clone, Copy, Reflect)]
+pub struct AndTrigger<A: Trigger, B: Trigger>(pub A, pub B);
+
+impl<A: Trigger, B: Trigger> Trigger for AndTrigger<A, B> {
+    type Param<'w, 's> = (A::Param<'w, 's>, B::Param<'w, 's>);
+    type Ok = ();
+    type Err = ();
+
+    fn trigger(&self, entity: Entity, (a, b): &Self::Param<'_, '_>) -> Result<(), ()> {
+        match (self.0.trigger(entity, a), self.1.trigger(entity, b)) {
+            (Ok(_), Ok(_)) => Ok(()),
+            _ => Err(()),
+        }
+    }
+}
+
+// Triggers when a boss has lost enough health for its next phase, gives the phase index
+#[derive(Clone, Copy, Reflect)]
+pub struct PhaseThreshold;
+
+impl Trigger for PhaseThreshold {
+    type Param<'w, 's> =
+        Query<'w, 's, (&'static Creature, &'static BossPhases), Without<PhaseTransition>>;
+    type Ok = usize;
+    type Err = ();
+
+    fn trigger(&self, entity: Entity, bosses: &Self::Param<'_, '_>) -> Result<usize, ()> {
+        let (creature, phases) = bosses.get(entity).map_err(|_| ())?;
+        phases
+            .next_phase(creature.health / creature.max_health)
+            .ok_or(())
+    }
+}
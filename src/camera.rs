@@ -7,11 +7,18 @@ pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(camera_movement_system)
+        app.init_resource::<CameraFocus>()
+            .add_system(camera_movement_system)
             .add_system(spawn_camera.on_startup());
     }
 }
 
+// Somewhere other than the player for the camera to pan to, e.g. a boss during its intro
+#[derive(Resource, Default)]
+pub struct CameraFocus {
+    pub target: Option<Vec2>,
+}
+
 pub fn spawn_camera(mut commands: Commands) {
     commands.spawn(Camera2dBundle {
         transform: Transform {
@@ -23,9 +30,22 @@ pub fn spawn_camera(mut commands: Commands) {
 }
 
 pub fn camera_movement_system(
+    time: Res<Time>,
+    camera_focus: Res<CameraFocus>,
     mut camera_query: Query<&mut Transform, (With<Camera>, Without<Player>)>,
     player_query: Query<&Transform, With<Player>>,
 ) {
+    if let Some(target) = camera_focus.target {
+        // Real time, the game might be paused while panning
+        let amount = (time.raw_delta_seconds() * 4.0).min(1.0);
+        for mut camera_transform in camera_query.iter_mut() {
+            let position = camera_transform.translation.truncate().lerp(target, amount);
+            camera_transform.translation.x = position.x;
+            camera_transform.translation.y = position.y;
+        }
+        return;
+    }
+
     for player_transform in player_query.iter() {
         for mut camera_transform in &mut camera_query.iter_mut() {
             camera_transform.translation.x = player_transform.translation.x;
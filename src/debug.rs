@@ -1,5 +1,5 @@
 use bevy::{
-    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    diagnostic::{Diagnostic, Diagnostics, FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     prelude::*,
 };
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
@@ -8,13 +8,15 @@ use bevy_rapier2d::render::RapierDebugRenderPlugin;
 
 use crate::{
     animation::Animated,
-    behaviour::separation::Separation,
+    behaviour::separation::{Separation, SEPARATION_TIME},
     entity::{
         creature::{Creature, Velocity},
+        faction::{Charmed, Faction},
         player::Player,
-        spawner::Spawner,
+        spawner::{EnemyType, Spawner},
+        Enemy,
     },
-    game::level_manager::*,
+    game::{level_manager::*, GameState},
 };
 
 #[derive(Resource, Default)]
@@ -37,7 +39,82 @@ impl Plugin for DebugPlugin {
                 .register_type::<LevelManager>()
                 .register_type::<Player>()
                 .register_type::<Separation>()
-                .init_resource::<DebugState>();
+                .init_resource::<DebugState>()
+                .add_startup_system(register_separation_diagnostic)
+                .add_system(separation_benchmark.in_set(OnUpdate(GameState::InLevel)))
+                .add_system(faction_debug.in_set(OnUpdate(GameState::InLevel)));
+        }
+    }
+}
+
+fn register_separation_diagnostic(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(Diagnostic::new(SEPARATION_TIME, "separation_time_ms", 20));
+}
+
+// F9 drops a crowd of Adepts next to the player. They keep summoning Skullers, so after a little
+// while there are hundreds of enemies to stress `separation_system`, watch `separation_time_ms`
+fn separation_benchmark(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F9) {
+        return;
+    }
+    if let Ok(player_transform) = player_query.get_single() {
+        commands.spawn((
+            SpawnerBundle {
+                spawner: Spawner {
+                    timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+                    spawn_rate: 5,
+                    spawn_count: 20,
+                    enemy_type: EnemyType::Adept,
+                    elite_chance: 0.0,
+                },
+            },
+            *player_transform,
+            GlobalTransform::default(),
+            LevelObject,
+        ));
+    }
+}
+
+// F10 summons a few friendly Skullers and F11 charms every enemy near the player, to try out
+// fights between factions
+fn faction_debug(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(Entity, &Transform), (With<Enemy>, Without<Player>)>,
+) {
+    let player_transform = match player_query.get_single() {
+        Ok(player_transform) => player_transform,
+        Err(_) => return,
+    };
+
+    if keyboard_input.just_pressed(KeyCode::F10) {
+        commands.spawn((
+            SpawnerBundle {
+                spawner: Spawner {
+                    timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+                    spawn_rate: 3,
+                    spawn_count: 3,
+                    enemy_type: EnemyType::Skuller,
+                    elite_chance: 0.0,
+                },
+            },
+            Faction::Player,
+            *player_transform,
+            GlobalTransform::default(),
+            LevelObject,
+        ));
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F11) {
+        for (entity, transform) in enemy_query.iter() {
+            if transform.translation.distance(player_transform.translation) < 160.0 {
+                commands.entity(entity).insert(Charmed::new(10.0));
+            }
         }
     }
 }
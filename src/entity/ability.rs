@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, Sensor};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    app_state::AppState,
+    game::{
+        mutation_manager::{MutationManager, MutationType},
+        GameState,
+    },
+    PIXELS_PER_METER,
+};
+
+use super::{
+    creature::Lifetime,
+    faction::HurtboxKnockback,
+    player::{Dead, Player, PlayerAction, PlayerHurtbox, PlayerHurtboxDamage, Rolling},
+};
+
+pub struct AbilityPlugin;
+
+impl Plugin for AbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(ability_slot_system.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                ability_use_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(ability_slot_system),
+            )
+            .add_system(ability_recharge_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(ability_effect_system.in_set(OnUpdate(GameState::InLevel)));
+    }
+}
+
+// Actives granted by mutations, on top of their passive effect
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActiveAbility {
+    // Repulsion, a wide ring that knocks everything away
+    Shockwave,
+    // HeavyBones, a tight, heavy hit around the player
+    GroundSlam,
+}
+
+impl ActiveAbility {
+    pub fn granted_by(mutation_type: MutationType) -> Option<ActiveAbility> {
+        match mutation_type {
+            MutationType::Repulsion => Some(ActiveAbility::Shockwave),
+            MutationType::HeavyBones => Some(ActiveAbility::GroundSlam),
+            _ => None,
+        }
+    }
+
+    fn base_charges(&self) -> u32 {
+        match self {
+            ActiveAbility::Shockwave => 2,
+            ActiveAbility::GroundSlam => 1,
+        }
+    }
+
+    fn recharge_seconds(&self) -> f32 {
+        match self {
+            ActiveAbility::Shockwave => 6.0,
+            ActiveAbility::GroundSlam => 8.0,
+        }
+    }
+
+    fn radius(&self) -> f32 {
+        match self {
+            ActiveAbility::Shockwave => PIXELS_PER_METER * 3.0,
+            ActiveAbility::GroundSlam => PIXELS_PER_METER * 2.0,
+        }
+    }
+
+    fn damage(&self) -> u32 {
+        match self {
+            ActiveAbility::Shockwave => 10,
+            ActiveAbility::GroundSlam => 35,
+        }
+    }
+
+    // Shockwave is mostly there to clear space, GroundSlam hits harder but pushes less
+    fn knockback(&self) -> f32 {
+        match self {
+            ActiveAbility::Shockwave => 1000.0,
+            ActiveAbility::GroundSlam => 450.0,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            ActiveAbility::Shockwave => Color::rgb(0.5, 0.8, 1.0),
+            ActiveAbility::GroundSlam => Color::rgb(0.8, 0.6, 0.4),
+        }
+    }
+}
+
+// The player's one active ability slot. Charges come back one at a time
+#[derive(Component)]
+pub struct AbilitySlot {
+    pub ability: Option<ActiveAbility>,
+    pub charges: u32,
+    pub max_charges: u32,
+    pub recharge: Timer,
+    pub player_mutations_version: Option<u32>,
+}
+
+impl Default for AbilitySlot {
+    fn default() -> Self {
+        Self {
+            ability: None,
+            charges: 0,
+            max_charges: 0,
+            recharge: Timer::from_seconds(1.0, TimerMode::Once),
+            player_mutations_version: None,
+        }
+    }
+}
+
+// Expanding ring drawn when an ability goes off
+#[derive(Component)]
+pub struct AbilityEffect;
+
+// The most recently taken mutation with an active fills the slot, each rank past the first adds
+// a charge
+pub fn ability_slot_system(
+    mut commands: Commands,
+    mutation_manager: Res<MutationManager>,
+    mut player_query: Query<(Entity, Option<&mut AbilitySlot>), With<Player>>,
+) {
+    for (entity, slot) in player_query.iter_mut() {
+        let mut slot = match slot {
+            Some(slot) => slot,
+            None => {
+                commands.entity(entity).insert(AbilitySlot::default());
+                continue;
+            }
+        };
+        if slot.player_mutations_version == Some(mutation_manager.player_mutations_version) {
+            continue;
+        }
+        slot.player_mutations_version = Some(mutation_manager.player_mutations_version);
+
+        let granted = mutation_manager
+            .player_mutations
+            .iter()
+            .rev()
+            .find_map(|m| {
+                ActiveAbility::granted_by(m.mutation_type).map(|ability| (ability, m.rank))
+            });
+        match granted {
+            Some((ability, rank)) => {
+                let max_charges = ability.base_charges() + rank.saturating_sub(1);
+                if slot.ability != Some(ability) {
+                    slot.ability = Some(ability);
+                    slot.charges = max_charges;
+                    slot.recharge =
+                        Timer::from_seconds(ability.recharge_seconds(), TimerMode::Once);
+                } else {
+                    // Ranking up hands over the new charge straight away
+                    slot.charges += max_charges.saturating_sub(slot.max_charges);
+                }
+                slot.max_charges = max_charges;
+            }
+            None => {
+                *slot = AbilitySlot {
+                    player_mutations_version: slot.player_mutations_version,
+                    ..default()
+                };
+            }
+        }
+    }
+}
+
+pub fn ability_recharge_system(time: Res<Time>, mut slot_query: Query<&mut AbilitySlot>) {
+    for mut slot in slot_query.iter_mut() {
+        if slot.charges >= slot.max_charges {
+            continue;
+        }
+        if slot.recharge.tick(time.delta()).finished() {
+            slot.charges += 1;
+            slot.recharge.reset();
+        }
+    }
+}
+
+pub fn ability_use_system(
+    mut commands: Commands,
+    mut player_query: Query<
+        (
+            &Transform,
+            &mut AbilitySlot,
+            &ActionState<PlayerAction>,
+            Option<&Rolling>,
+        ),
+        (With<Player>, Without<Dead>),
+    >,
+) {
+    for (transform, mut slot, action_state, rolling) in player_query.iter_mut() {
+        if !action_state.just_pressed(PlayerAction::Ability) || rolling.is_some() {
+            continue;
+        }
+        let ability = match slot.ability {
+            Some(ability) if slot.charges > 0 => ability,
+            _ => continue,
+        };
+        slot.charges -= 1;
+
+        let radius = ability.radius();
+        let position = transform.translation.truncate();
+        commands.spawn((
+            PlayerHurtbox {
+                collider: Collider::ball(radius),
+                damage: PlayerHurtboxDamage(ability.damage()),
+                sensor: Sensor,
+                transform: Transform::from_translation(position.extend(0.0)),
+                ..default()
+            },
+            HurtboxKnockback(ability.knockback()),
+            ActiveCollisionTypes::STATIC_STATIC,
+            Lifetime {
+                timer: Timer::from_seconds(0.15, TimerMode::Once),
+            },
+        ));
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Circle {
+                    radius,
+                    center: Vec2::ZERO,
+                }),
+                // Above the level but under every creature
+                transform: Transform::from_translation(position.extend(-10.0))
+                    .with_scale(Vec3::splat(0.2)),
+                ..default()
+            },
+            Fill::color(ability.color().with_a(0.2)),
+            Stroke::new(ability.color(), 3.0),
+            AbilityEffect,
+            Lifetime {
+                timer: Timer::from_seconds(0.3, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+pub fn ability_effect_system(
+    mut effect_query: Query<
+        (&Lifetime, &mut Transform, &mut Fill, &mut Stroke),
+        With<AbilityEffect>,
+    >,
+) {
+    for (lifetime, mut transform, mut fill, mut stroke) in effect_query.iter_mut() {
+        // Grows out to the full radius while fading
+        let progress = lifetime.timer.percent();
+        transform.scale = Vec3::splat(0.2 + 0.8 * progress);
+        fill.color.set_a(0.2 * (1.0 - progress));
+        stroke.color.set_a(1.0 - progress);
+    }
+}
@@ -3,13 +3,14 @@ use bevy_rapier2d::prelude::{Collider, Sensor};
 
 use crate::{
     animation::Animated,
-    behaviour::separation::{separation_system, Separation},
+    behaviour::{separation::Separation, target::Leader},
     game::{level_manager::SpawnerBundle, GameState},
     PIXELS_PER_METER,
 };
 
 use super::{
     creature::{Creature, CreatureBundle, Hitbox, Velocity},
+    projectile::{Projectile, RangedAttack},
     spawner::{EnemyType, Spawner},
     Enemy, ZSort,
 };
@@ -18,8 +19,7 @@ pub struct AdeptPlugin;
 
 impl Plugin for AdeptPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(separation_system::<Adept>)
-            .add_system(summon_skullers.in_set(OnUpdate(GameState::InLevel)));
+        app.add_system(summon_skullers.in_set(OnUpdate(GameState::InLevel)));
     }
 }
 
@@ -35,6 +35,9 @@ pub struct AdeptBundle {
     pub name: Name,
     pub adept: Adept,
     pub separation: Separation,
+    // Other enemies can target it with `TargetRole::Leader`
+    pub leader: Leader,
+    pub ranged_attack: RangedAttack,
 }
 
 impl AdeptBundle {
@@ -74,6 +77,7 @@ impl AdeptBundle {
                 skuller_timer: Timer::from_seconds(5.0, TimerMode::Repeating),
             },
             name: Name::new("Adept"),
+            ranged_attack: RangedAttack::new(Projectile::AdeptBolt, PIXELS_PER_METER * 12.0, 2.5),
             separation: Separation {
                 radius: PIXELS_PER_METER * 1.0,
                 separation_force: 1000.0,
@@ -81,6 +85,7 @@ impl AdeptBundle {
                 max_speed_reset: Some(150.0),
                 ..default()
             },
+            leader: Leader,
         }
     }
 }
@@ -100,6 +105,7 @@ pub fn summon_skullers(
                         spawn_rate: 5,
                         spawn_count: 5,
                         enemy_type: EnemyType::Skuller,
+                        elite_chance: 0.0,
                     },
                 },
                 *adept_transform,
@@ -9,6 +9,7 @@ use rand::Rng;
 use crate::app_state::loading::SpriteAssets;
 use crate::game::mutation_manager::MutationManager;
 use crate::game::mutation_manager::MutationType;
+use crate::game::mutation_manager::SynergyType;
 
 use crate::{
     animation::Animated,
@@ -19,11 +20,13 @@ use crate::{
     game::GameState,
 };
 
+use super::player::Dead;
 use super::player::Facing;
 use super::player::LastFacing;
 use super::player::PlayerHurtbox;
 use super::player::PlayerHurtboxDamage;
 use super::player::Rage;
+use super::spawner::{EnemyKind, EnemyType};
 use super::{player::Player, ZSort};
 
 #[derive(Component, Reflect)]
@@ -103,9 +106,17 @@ pub struct DealDamage {
     pub knockback_force: f32,
 }
 
+// Sent whenever a non-player creature's health reaches zero
+pub struct EnemyKilled {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub enemy_type: Option<EnemyType>,
+}
+
 impl Plugin for CreaturePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(apply_friction_system)
+        app.add_event::<EnemyKilled>()
+            .add_system(apply_friction_system)
             .add_system(apply_velocity_system)
             .add_system(z_ordering_system)
             .add_system(set_sprite_facing_system)
@@ -310,24 +321,41 @@ pub fn deal_damage_system(
         &DealDamage,
         Option<&Player>,
         &Transform,
+        Option<&EnemyKind>,
+        Option<&Vulnerable>,
+        Option<&Invulnerable>,
     )>,
     rage_query: Query<&Rage, With<Player>>,
-    mut next_state: ResMut<NextState<AppState>>,
     sfx: Res<AudioChannel<SoundEffects>>,
     music_assets: Res<SfxAssets>,
     sprite_assets: Res<SpriteAssets>,
     mut mutation_manager: ResMut<MutationManager>,
+    mut enemy_killed: EventWriter<EnemyKilled>,
+    mut next_game_state: ResMut<NextState<GameState>>,
 ) {
-    for (entity, mut creature, mut velocity, damage, player, transform) in query.iter_mut() {
-        if creature.damage_invulnerability.finished() {
+    for (
+        entity,
+        mut creature,
+        mut velocity,
+        damage,
+        player,
+        transform,
+        enemy_kind,
+        vulnerable,
+        invulnerable,
+    ) in query.iter_mut()
+    {
+        if creature.damage_invulnerability.finished() && invulnerable.is_none() {
             creature.damage_invulnerability.reset();
             let mut damage_amount = damage.amount;
             let mut knockback_multiplier = 1.0;
             let mut knockback_duration = 0.5;
             // If it's the player being hit
             if let Some(_player) = player {
-                if mutation_manager.has_mutation(MutationType::DrySkin) {
-                    for _ in 0..5 {
+                // Five flakes per rank
+                let dry_skin = mutation_manager.mutation_rank(MutationType::DrySkin);
+                if dry_skin > 0 {
+                    for _ in 0..5 * dry_skin {
                         let random_x = rand::thread_rng().gen_range(-1.0..1.0);
                         let random_y = rand::thread_rng().gen_range(0.0..1.0) + 1.0; // Ensure a slightly upward direction
 
@@ -365,18 +393,25 @@ pub fn deal_damage_system(
                     damage_amount += 2.0;
                 }
 
-                if mutation_manager.has_mutation(MutationType::Rage) {
+                let rage = mutation_manager.mutation_rank(MutationType::Rage);
+                if rage > 0 {
                     let mut rng = rand::thread_rng();
                     let random: f32 = rng.gen_range(0.0..1.0);
-                    if random < 0.2 {
+                    let mut rage_chance = 0.1 + 0.1 * rage as f32;
+                    // Berserker doubles it, up to a 4 in 5 chance at max rank
+                    if mutation_manager.has_synergy(SynergyType::Berserker) {
+                        rage_chance *= 2.0;
+                    }
+                    if random < rage_chance {
                         commands.entity(entity).insert(Rage {
                             timer: Timer::from_seconds(2.5, TimerMode::Once),
                         });
                     }
                 }
             } else {
-                if mutation_manager.has_mutation(MutationType::Repulsion) {
-                    knockback_multiplier = 8.0;
+                let repulsion = mutation_manager.mutation_rank(MutationType::Repulsion);
+                if repulsion > 0 {
+                    knockback_multiplier = 4.0 + 4.0 * repulsion as f32;
                 }
                 if let Ok(_rage) = rage_query.get_single() {
                     damage_amount *= 2.0;
@@ -384,6 +419,10 @@ pub fn deal_damage_system(
                 }
             }
 
+            if let Some(vulnerable) = vulnerable {
+                damage_amount *= vulnerable.damage_multiplier;
+            }
+
             creature.health -= damage_amount;
 
             sfx.play(music_assets.hit.clone()).with_volume(0.5);
@@ -398,8 +437,14 @@ pub fn deal_damage_system(
                     } else {
                         sfx.play(music_assets.laff1.clone());
                     }
-                    next_state.set(AppState::MainMenu);
+                    commands.entity(entity).insert(Dead);
+                    next_game_state.set(GameState::GameOver);
                 } else {
+                    enemy_killed.send(EnemyKilled {
+                        entity,
+                        position: transform.translation.truncate(),
+                        enemy_type: enemy_kind.map(|enemy_kind| enemy_kind.0.clone()),
+                    });
                     commands.entity(entity).despawn_recursive();
                 }
             } else {
@@ -415,6 +460,16 @@ pub fn deal_damage_system(
     }
 }
 
+// Damage taken is multiplied while this is present
+#[derive(Component, Reflect)]
+pub struct Vulnerable {
+    pub damage_multiplier: f32,
+}
+
+// No damage is taken at all while this is present
+#[derive(Component, Reflect, Default)]
+pub struct Invulnerable;
+
 #[derive(Component, Reflect)]
 pub struct Knockback {
     pub timer: Timer,
@@ -508,8 +563,8 @@ pub fn bleed_system(
 
 #[derive(Component, Reflect)]
 pub struct ChangeColor {
-    color: Color,
-    timer: Timer,
+    pub color: Color,
+    pub timer: Timer,
 }
 
 #[derive(Component, Reflect)]
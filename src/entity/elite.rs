@@ -0,0 +1,454 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, RapierContext, Sensor};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    behaviour::separation::Separation,
+    game::{
+        arena::Arena,
+        level_manager::{LevelObject, SpawnerBundle},
+        GameState,
+    },
+    PIXELS_PER_METER,
+};
+
+use super::{
+    creature::{
+        damage_invulnerability_system, deal_damage_system, Bleed, ChangeColor, Creature,
+        DealDamage, EnemyKilled, Heal, Invulnerable, Lifetime,
+    },
+    player::Player,
+    spawner::{EnemyKind, EnemyType, Spawner},
+    Enemy,
+};
+
+// Chance for an elite to roll a second modifier
+const SECOND_MODIFIER_CHANCE: f64 = 0.25;
+
+// Extra arena score and healing for the player per modifier on a killed elite
+const ELITE_KILL_SCORE: u32 = 25;
+const ELITE_KILL_HEAL: f32 = 4.0;
+
+pub struct ElitePlugin;
+
+impl Plugin for ElitePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(elite_marker_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(
+                elite_tint_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(damage_invulnerability_system),
+            )
+            .add_system(
+                armored_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .before(deal_damage_system),
+            )
+            .add_system(fast_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(poison_trail_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(poison_pool_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(summoner_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(shielded_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(
+                elite_killed_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(deal_damage_system),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EliteModifier {
+    Armored,
+    Fast,
+    Splitting,
+    PoisonTrail,
+    Summoner,
+    Shielded,
+}
+
+impl EliteModifier {
+    pub const ALL: [EliteModifier; 6] = [
+        EliteModifier::Armored,
+        EliteModifier::Fast,
+        EliteModifier::Splitting,
+        EliteModifier::PoisonTrail,
+        EliteModifier::Summoner,
+        EliteModifier::Shielded,
+    ];
+
+    pub fn color(&self) -> Color {
+        match self {
+            EliteModifier::Armored => Color::rgb(0.6, 0.6, 0.7),
+            EliteModifier::Fast => Color::rgb(1.0, 0.9, 0.3),
+            EliteModifier::Splitting => Color::rgb(1.0, 0.5, 0.9),
+            EliteModifier::PoisonTrail => Color::rgb(0.4, 1.0, 0.3),
+            EliteModifier::Summoner => Color::rgb(0.7, 0.4, 1.0),
+            EliteModifier::Shielded => Color::rgb(0.3, 0.7, 1.0),
+        }
+    }
+
+    fn insert(&self, entity_commands: &mut EntityCommands) {
+        match self {
+            EliteModifier::Armored => entity_commands.insert(Armored::default()),
+            EliteModifier::Fast => entity_commands.insert(Fast::default()),
+            EliteModifier::Splitting => entity_commands.insert(Splitting::default()),
+            EliteModifier::PoisonTrail => entity_commands.insert(PoisonTrail::default()),
+            EliteModifier::Summoner => entity_commands.insert(Summoner::default()),
+            EliteModifier::Shielded => entity_commands.insert(Shielded),
+        };
+    }
+}
+
+// An enemy that rolled one or more modifiers when it was spawned
+#[derive(Component, Clone, Debug)]
+pub struct Elite {
+    pub modifiers: Vec<EliteModifier>,
+}
+
+impl Elite {
+    // The modifier colors blended together
+    pub fn tint(&self) -> Color {
+        let sum = self.modifiers.iter().fold(Vec4::ZERO, |sum, modifier| {
+            sum + Vec4::from(modifier.color())
+        });
+        Color::from(sum / self.modifiers.len().max(1) as f32)
+    }
+}
+
+// Gives the enemy one or two random modifiers
+pub fn roll_elite(commands: &mut Commands, enemy: Entity, rng: &mut impl Rng) {
+    let count = if rng.gen_bool(SECOND_MODIFIER_CHANCE) {
+        2
+    } else {
+        1
+    };
+    let modifiers: Vec<EliteModifier> = EliteModifier::ALL
+        .choose_multiple(rng, count)
+        .copied()
+        .collect();
+
+    let mut entity_commands = commands.entity(enemy);
+    for modifier in modifiers.iter() {
+        modifier.insert(&mut entity_commands);
+    }
+    entity_commands.insert(Elite { modifiers });
+}
+
+// Ring drawn under elites in the color of their first modifier
+#[derive(Component)]
+pub struct EliteMarker;
+
+// Bubble around shielded elites, only visible while the shield is up
+#[derive(Component)]
+pub struct ShieldBubble;
+
+pub fn elite_marker_system(
+    mut commands: Commands,
+    elite_query: Query<(Entity, &Elite), Added<Elite>>,
+) {
+    for (entity, elite) in elite_query.iter() {
+        let color = elite
+            .modifiers
+            .first()
+            .map(|modifier| modifier.color())
+            .unwrap_or(Color::WHITE);
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shapes::Circle {
+                        radius: PIXELS_PER_METER * 0.6,
+                        center: Vec2::ZERO,
+                    }),
+                    transform: Transform::from_xyz(0.0, -PIXELS_PER_METER * 0.4, -0.5),
+                    ..default()
+                },
+                Stroke::new(color, 2.0),
+                EliteMarker,
+            ));
+
+            if elite.modifiers.contains(&EliteModifier::Shielded) {
+                parent.spawn((
+                    ShapeBundle {
+                        path: GeometryBuilder::build_as(&shapes::Circle {
+                            radius: PIXELS_PER_METER * 0.9,
+                            center: Vec2::ZERO,
+                        }),
+                        transform: Transform::from_xyz(0.0, 0.0, 0.5),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    Fill::color(Color::rgba(0.3, 0.7, 1.0, 0.2)),
+                    Stroke::new(EliteModifier::Shielded.color(), 1.0),
+                    ShieldBubble,
+                ));
+            }
+        });
+    }
+}
+
+// Hit flashes and heals take priority over the tint
+pub fn elite_tint_system(
+    mut elite_query: Query<(&Elite, &mut TextureAtlasSprite), Without<ChangeColor>>,
+) {
+    for (elite, mut sprite) in elite_query.iter_mut() {
+        sprite.color = elite.tint();
+    }
+}
+
+// Takes less damage and knockback
+#[derive(Component)]
+pub struct Armored {
+    pub damage_multiplier: f32,
+    pub knockback_multiplier: f32,
+}
+
+impl Default for Armored {
+    fn default() -> Self {
+        Self {
+            damage_multiplier: 0.5,
+            knockback_multiplier: 0.25,
+        }
+    }
+}
+
+pub fn armored_system(mut armored_query: Query<(&Armored, &mut DealDamage), Changed<DealDamage>>) {
+    for (armored, mut damage) in armored_query.iter_mut() {
+        damage.amount *= armored.damage_multiplier;
+        damage.knockback_force *= armored.knockback_multiplier;
+    }
+}
+
+#[derive(Component)]
+pub struct Fast {
+    pub speed_multiplier: f32,
+}
+
+impl Default for Fast {
+    fn default() -> Self {
+        Self {
+            speed_multiplier: 1.6,
+        }
+    }
+}
+
+pub fn fast_system(
+    mut fast_query: Query<(&Fast, &mut Creature, Option<&mut Separation>), Added<Fast>>,
+) {
+    for (fast, mut creature, separation) in fast_query.iter_mut() {
+        creature.max_speed *= fast.speed_multiplier;
+        creature.acceleration *= fast.speed_multiplier;
+
+        // Same as difficulty scaling, otherwise separation would undo it
+        if let Some(mut separation) = separation {
+            if let Some(max_speed_reset) = separation.max_speed_reset {
+                separation.max_speed_reset = Some(max_speed_reset * fast.speed_multiplier);
+            }
+        }
+    }
+}
+
+// Splits into regular enemies of the same type on death
+#[derive(Component)]
+pub struct Splitting {
+    pub count: usize,
+}
+
+impl Default for Splitting {
+    fn default() -> Self {
+        Self { count: 2 }
+    }
+}
+
+// Leaves pools behind that make the player bleed
+#[derive(Component)]
+pub struct PoisonTrail {
+    pub timer: Timer,
+}
+
+impl Default for PoisonTrail {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(1.2, TimerMode::Repeating),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PoisonPool;
+
+pub fn poison_trail_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut trail_query: Query<(&Transform, &mut PoisonTrail)>,
+) {
+    for (transform, mut trail) in trail_query.iter_mut() {
+        if !trail.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let radius = PIXELS_PER_METER * 0.75;
+        let position = transform.translation.truncate() - Vec2::Y * PIXELS_PER_METER * 0.4;
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Circle {
+                    radius,
+                    center: Vec2::ZERO,
+                }),
+                // Above the level but under every creature
+                transform: Transform::from_translation(position.extend(-10.0)),
+                ..default()
+            },
+            Fill::color(Color::rgba(0.3, 0.9, 0.2, 0.35)),
+            Collider::ball(radius),
+            Sensor,
+            ActiveCollisionTypes::STATIC_STATIC,
+            Lifetime {
+                timer: Timer::from_seconds(4.0, TimerMode::Once),
+            },
+            PoisonPool,
+            LevelObject,
+        ));
+    }
+}
+
+pub fn poison_pool_system(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    pool_query: Query<Entity, With<PoisonPool>>,
+    player_query: Query<Entity, (With<Player>, Without<Bleed>)>,
+) {
+    for player in player_query.iter() {
+        if pool_query
+            .iter()
+            .any(|pool| rapier_context.intersection_pair(pool, player) == Some(true))
+        {
+            commands.entity(player).insert(Bleed {
+                damage: 1.0,
+                ticks: 3,
+                tick_timer: Timer::from_seconds(0.8, TimerMode::Once),
+            });
+        }
+    }
+}
+
+// Opens a small portal of skullers every so often
+#[derive(Component)]
+pub struct Summoner {
+    pub timer: Timer,
+}
+
+impl Default for Summoner {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(6.0, TimerMode::Repeating),
+        }
+    }
+}
+
+pub fn summoner_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut summoner_query: Query<(&Transform, &mut Summoner)>,
+) {
+    for (transform, mut summoner) in summoner_query.iter_mut() {
+        if summoner.timer.tick(time.delta()).just_finished() {
+            commands.spawn((
+                SpawnerBundle {
+                    spawner: Spawner {
+                        timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+                        spawn_rate: 2,
+                        spawn_count: 2,
+                        enemy_type: EnemyType::Skuller,
+                        elite_chance: 0.0,
+                    },
+                },
+                *transform,
+                GlobalTransform::default(),
+                LevelObject,
+            ));
+        }
+    }
+}
+
+// Can't be hurt while any enemy without a shield is still alive
+#[derive(Component)]
+pub struct Shielded;
+
+pub fn shielded_system(
+    mut commands: Commands,
+    shielded_query: Query<(Entity, Option<&Children>, Option<&Invulnerable>), With<Shielded>>,
+    unshielded_query: Query<(), (With<Enemy>, Without<Shielded>)>,
+    mut bubble_query: Query<&mut Visibility, With<ShieldBubble>>,
+) {
+    let shield_up = !unshielded_query.is_empty();
+    for (entity, children, invulnerable) in shielded_query.iter() {
+        match (shield_up, invulnerable.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert(Invulnerable);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Invulnerable>();
+            }
+            _ => {}
+        }
+
+        for child in children.into_iter().flatten() {
+            if let Ok(mut visibility) = bubble_query.get_mut(*child) {
+                *visibility = if shield_up {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
+}
+
+// Elites are still around when they're killed since the despawn is deferred, so splitting and the
+// kill rewards can look at them here
+pub fn elite_killed_system(
+    mut commands: Commands,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    elite_query: Query<(&Elite, &Transform, Option<&EnemyKind>, Option<&Splitting>)>,
+    player_query: Query<Entity, With<Player>>,
+    mut arena: Option<ResMut<Arena>>,
+) {
+    for killed in enemy_killed.iter() {
+        let (elite, transform, enemy_kind, splitting) = match elite_query.get(killed.entity) {
+            Ok(elite) => elite,
+            Err(_) => continue,
+        };
+        let modifiers = elite.modifiers.len();
+
+        if let (Some(splitting), Some(enemy_kind)) = (splitting, enemy_kind) {
+            commands.spawn((
+                SpawnerBundle {
+                    spawner: Spawner {
+                        timer: Timer::from_seconds(0.05, TimerMode::Repeating),
+                        spawn_rate: splitting.count,
+                        spawn_count: splitting.count,
+                        enemy_type: enemy_kind.0.clone(),
+                        elite_chance: 0.0,
+                    },
+                },
+                *transform,
+                GlobalTransform::default(),
+                LevelObject,
+            ));
+        }
+
+        if let Ok(player) = player_query.get_single() {
+            commands.entity(player).insert(Heal {
+                amount: ELITE_KILL_HEAL * modifiers as f32,
+                ticks: 1,
+                tick_timer: Timer::from_seconds(0.1, TimerMode::Once),
+            });
+        }
+
+        if let Some(arena) = arena.as_mut() {
+            arena.score += ELITE_KILL_SCORE * modifiers as u32;
+        }
+    }
+}
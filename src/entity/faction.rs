@@ -0,0 +1,230 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierContext;
+
+use crate::game::{
+    mutation_manager::{MutationManager, MutationType},
+    GameState,
+};
+
+use super::{
+    creature::{damage_invulnerability_system, Bleed, ChangeColor, Creature, DealDamage, Heal},
+    elite::elite_tint_system,
+    player::{Immune, Player, PlayerHurtboxDamage},
+    EnemyHurtboxDamage,
+};
+
+pub struct FactionPlugin;
+
+impl Plugin for FactionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(hit_resolution_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(
+                charmed_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(damage_invulnerability_system)
+                    .after(elite_tint_system),
+            );
+    }
+}
+
+// Which side a creature or hurtbox is on. Creatures without one are on the player's side if
+// they're the player and on the enemies' otherwise, see `Faction::of_creature`
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Faction {
+    Player,
+    #[default]
+    Enemy,
+    // Hazards, they hurt everyone and everyone can hurt them
+    Neutral,
+}
+
+impl Faction {
+    // Whether hurtboxes of this faction damage creatures of the other
+    pub fn is_hostile_to(&self, other: Faction) -> bool {
+        *self == Faction::Neutral || *self != other
+    }
+
+    pub fn of_creature(faction: Option<&Faction>, player: Option<&Player>) -> Faction {
+        match (faction, player) {
+            (Some(faction), _) => *faction,
+            (None, Some(_)) => Faction::Player,
+            (None, None) => Faction::Enemy,
+        }
+    }
+}
+
+// Overrides how hard a hurtbox knocks its victims back
+#[derive(Component, Clone, Copy)]
+pub struct HurtboxKnockback(pub f32);
+
+// Every creature against the hurtboxes it's touching and hostile to. Hurtboxes without a faction
+// take their parent's, e.g. attacks spawned as children of a charmed enemy, and otherwise go by
+// which hurtbox damage they carry
+pub fn hit_resolution_system(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    victim_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&Faction>,
+            Option<&Player>,
+            Option<&Immune>,
+        ),
+        With<Creature>,
+    >,
+    hurtbox_query: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            Option<&Faction>,
+            Option<&Parent>,
+            Option<&PlayerHurtboxDamage>,
+            Option<&EnemyHurtboxDamage>,
+            Option<&HurtboxKnockback>,
+        ),
+        Or<(With<PlayerHurtboxDamage>, With<EnemyHurtboxDamage>)>,
+    >,
+    faction_query: Query<&Faction>,
+    player_query: Query<Entity, With<Player>>,
+    mutation_manager: Res<MutationManager>,
+) {
+    for (victim, victim_transform, victim_faction, victim_player, immune) in victim_query.iter() {
+        if immune.is_some() {
+            continue;
+        }
+        let victim_faction = Faction::of_creature(victim_faction, victim_player);
+
+        // Only what the victim is actually touching, so crowds don't check every pair
+        for (collider1, collider2, intersecting) in rapier_context.intersections_with(victim) {
+            if !intersecting {
+                continue;
+            }
+            let other = if collider1 == victim {
+                collider2
+            } else {
+                collider1
+            };
+            let (
+                hurtbox,
+                hurtbox_transform,
+                hurtbox_faction,
+                parent,
+                player_damage,
+                enemy_damage,
+                knockback,
+            ) = match hurtbox_query.get(other) {
+                Ok(hurtbox) => hurtbox,
+                Err(_) => continue,
+            };
+
+            // Nothing hurts itself with its own body or attacks
+            let parent = parent.map(|parent| parent.get());
+            if hurtbox == victim || parent == Some(victim) {
+                continue;
+            }
+
+            let (damage, from_player) = match (player_damage, enemy_damage) {
+                (Some(damage), _) => (damage.0, true),
+                (None, Some(damage)) => (damage.0, false),
+                (None, None) => continue,
+            };
+            let hurtbox_faction = hurtbox_faction
+                .or_else(|| parent.and_then(|parent| faction_query.get(parent).ok()))
+                .copied()
+                .unwrap_or(if from_player {
+                    Faction::Player
+                } else {
+                    Faction::Enemy
+                });
+            if !hurtbox_faction.is_hostile_to(victim_faction) {
+                continue;
+            }
+
+            commands.entity(victim).insert(DealDamage {
+                amount: damage as f32,
+                knockback_direction: (victim_transform.translation.truncate()
+                    - hurtbox_transform.translation().truncate())
+                .normalize_or_zero(),
+                knockback_force: knockback.map_or(250.0, |knockback| knockback.0),
+            });
+
+            // If the player has Hemophilia, they bleed when hit and so does whatever they hit
+            if mutation_manager.has_mutation(MutationType::Hemophilia) {
+                if victim_player.is_some() {
+                    commands.entity(victim).insert(Bleed {
+                        damage: 1.0,
+                        ticks: 3,
+                        tick_timer: Timer::from_seconds(1.5, TimerMode::Once),
+                    });
+                } else if from_player {
+                    // Higher ranks make whatever they hit bleed for longer
+                    let damage = (damage as f32 / 10.0).ceil() as u32;
+                    commands.entity(victim).insert(Bleed {
+                        damage: damage as f32,
+                        ticks: 2 + mutation_manager.mutation_rank(MutationType::Hemophilia),
+                        tick_timer: Timer::from_seconds(1.5, TimerMode::Once),
+                    });
+                }
+            }
+
+            // If the player has Vampirism
+            if from_player && victim_player.is_none() {
+                if let Ok(player) = player_query.get_single() {
+                    let vampirism = mutation_manager.mutation_rank(MutationType::Vampirism);
+                    if vampirism > 0 {
+                        commands.entity(player).insert(Heal {
+                            amount: vampirism as f32,
+                            ticks: 3,
+                            tick_timer: Timer::from_seconds(1.0, TimerMode::Once),
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Enemies that fight for the player until the timer runs out
+#[derive(Component)]
+pub struct Charmed {
+    pub timer: Timer,
+}
+
+impl Charmed {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+pub fn charmed_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut charmed_query: Query<(
+        Entity,
+        &mut Charmed,
+        &mut TextureAtlasSprite,
+        Option<&ChangeColor>,
+    )>,
+) {
+    for (entity, mut charmed, mut sprite, change_color) in charmed_query.iter_mut() {
+        if charmed.timer.elapsed_secs() == 0.0 {
+            commands.entity(entity).insert(Faction::Player);
+        }
+
+        if charmed.timer.tick(time.delta()).finished() {
+            commands
+                .entity(entity)
+                .remove::<Charmed>()
+                .insert(Faction::Enemy);
+            sprite.color = Color::WHITE;
+            continue;
+        }
+
+        if change_color.is_none() {
+            sprite.color = Color::rgb(1.0, 0.5, 0.8);
+        }
+    }
+}
@@ -2,25 +2,13 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::{Collider, Sensor};
 use rand::Rng;
 
-use crate::{
-    animation::Animated,
-    behaviour::separation::{separation_system, Separation},
-    PIXELS_PER_METER,
-};
+use crate::{animation::Animated, behaviour::separation::Separation, PIXELS_PER_METER};
 
 use super::{
     creature::{Creature, CreatureBundle, Hitbox, Velocity},
     Enemy, EnemyHurtboxDamage, ZSort,
 };
 
-pub struct GoblinPlugin;
-
-impl Plugin for GoblinPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_system(separation_system::<Goblin>);
-    }
-}
-
 #[derive(Component, Reflect, Default)]
 pub struct Goblin;
 
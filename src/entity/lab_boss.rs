@@ -1,28 +1,35 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::{Collider, Sensor};
+use seldom_state::prelude::{NotTrigger, StateMachine};
 
 use crate::{
     animation::Animated,
-    behaviour::separation::{separation_system, Separation},
+    behaviour::{
+        boss_phase::{BossPhase, BossPhases},
+        separation::Separation,
+        states::{
+            approach_and_keep_distance::ApproachAndKeepDistance,
+            attack::{AttackAndKeepDistance, LabBossAttack},
+            dash::Dash,
+            fire_projectile::{FireProjectile, FireProjectileAndKeepDistance},
+            idle::Idle,
+        },
+        target::TargetRole,
+        triggers::{AndTrigger, CanDash, DashFinished, LineOfSight, Near},
+    },
     PIXELS_PER_METER,
 };
 
 use super::{
     creature::{Creature, CreatureBundle, Hitbox, Velocity},
+    projectile::{Projectile, RangedAttack},
     Enemy, EnemyHurtboxDamage, ZSort,
 };
 
-pub struct LabBossPlugin;
-
-impl Plugin for LabBossPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_system(separation_system::<LabBoss>);
-    }
-}
-
 #[derive(Component, Reflect, Default)]
 pub struct LabBoss {
-    pub attack_timer: Timer,
+    // Time between slams, including the wind up
+    pub attack_cooldown: Timer,
 }
 
 #[derive(Bundle)]
@@ -68,7 +75,7 @@ impl LabBossBundle {
             },
             enemy: Enemy,
             lab_boss: LabBoss {
-                attack_timer: Timer::from_seconds(0.05, TimerMode::Once),
+                attack_cooldown: Timer::from_seconds(1.2, TimerMode::Once),
             },
             name: Name::new("LabBoss"),
             separation: Separation {
@@ -81,3 +88,175 @@ impl LabBossBundle {
         }
     }
 }
+
+// Melee only at first, backs off and sprays sludge at 60% health and charges around below 25%
+pub fn lab_boss_phases(target: TargetRole) -> BossPhases {
+    BossPhases::new(
+        target,
+        vec![
+            BossPhase {
+                health_threshold: 1.0,
+                state_machine: lab_boss_melee,
+                ranged_attack: None,
+                speed_multiplier: 1.0,
+            },
+            BossPhase {
+                health_threshold: 0.6,
+                state_machine: lab_boss_sprayer,
+                ranged_attack: Some(RangedAttack::new(
+                    Projectile::LabBossSludge,
+                    PIXELS_PER_METER * 10.0,
+                    3.5,
+                )),
+                speed_multiplier: 1.2,
+            },
+            BossPhase {
+                health_threshold: 0.25,
+                state_machine: lab_boss_frenzy,
+                ranged_attack: Some(RangedAttack::new(
+                    Projectile::LabBossSludge,
+                    PIXELS_PER_METER * 10.0,
+                    2.0,
+                )),
+                speed_multiplier: 1.4,
+            },
+        ],
+        90.0,
+    )
+}
+
+fn lab_boss_melee(target: TargetRole) -> StateMachine {
+    // The initial state is `Idle`
+    StateMachine::new(Idle)
+        .trans::<Idle>(
+            Near {
+                target,
+                range: PIXELS_PER_METER * 150.0,
+            },
+            ApproachAndKeepDistance {
+                target,
+                inner_distance: PIXELS_PER_METER * 3.5,
+                outer_distance: PIXELS_PER_METER * 5.0,
+            },
+        )
+        .trans::<ApproachAndKeepDistance>(
+            Near {
+                target,
+                range: PIXELS_PER_METER * 4.0,
+            },
+            AttackAndKeepDistance {
+                approach_and_keep_distance: ApproachAndKeepDistance {
+                    target,
+                    inner_distance: PIXELS_PER_METER * 1.0,
+                    outer_distance: PIXELS_PER_METER * 1.5,
+                },
+                attack: LabBossAttack,
+            },
+        )
+        .trans::<AttackAndKeepDistance>(
+            NotTrigger(Near {
+                target,
+                range: PIXELS_PER_METER * 3.0,
+            }),
+            ApproachAndKeepDistance {
+                target,
+                inner_distance: PIXELS_PER_METER * 3.5,
+                outer_distance: PIXELS_PER_METER * 5.0,
+            },
+        )
+}
+
+// Keeps its distance and sprays sludge, only slamming when the player gets in close, then backs
+// off and sprays again
+fn lab_boss_sprayer(target: TargetRole) -> StateMachine {
+    let keep_distance = ApproachAndKeepDistance {
+        target,
+        inner_distance: PIXELS_PER_METER * 6.0,
+        outer_distance: PIXELS_PER_METER * 9.0,
+    };
+    let slam = AttackAndKeepDistance {
+        approach_and_keep_distance: ApproachAndKeepDistance {
+            target,
+            inner_distance: PIXELS_PER_METER * 1.0,
+            outer_distance: PIXELS_PER_METER * 1.5,
+        },
+        attack: LabBossAttack,
+    };
+
+    StateMachine::new(Idle)
+        .trans::<Idle>(
+            Near {
+                target,
+                range: PIXELS_PER_METER * 150.0,
+            },
+            keep_distance.clone(),
+        )
+        .trans::<ApproachAndKeepDistance>(
+            Near {
+                target,
+                range: PIXELS_PER_METER * 2.5,
+            },
+            slam.clone(),
+        )
+        // A volley as soon as it's out of reach with a clear shot
+        .trans::<ApproachAndKeepDistance>(
+            AndTrigger(
+                NotTrigger(Near {
+                    target,
+                    range: PIXELS_PER_METER * 5.0,
+                }),
+                LineOfSight {
+                    target,
+                    range: PIXELS_PER_METER * 20.0,
+                },
+            ),
+            FireProjectileAndKeepDistance {
+                fire_projectile: FireProjectile {
+                    target,
+                    projectile: Projectile::LabBossSludge,
+                },
+                keep_distance: keep_distance.clone(),
+            },
+        )
+        .trans::<FireProjectileAndKeepDistance>(
+            Near {
+                target,
+                range: PIXELS_PER_METER * 2.5,
+            },
+            slam,
+        )
+        .trans::<FireProjectileAndKeepDistance>(
+            NotTrigger(LineOfSight {
+                target,
+                range: PIXELS_PER_METER * 20.0,
+            }),
+            keep_distance.clone(),
+        )
+        .trans::<AttackAndKeepDistance>(
+            NotTrigger(Near {
+                target,
+                range: PIXELS_PER_METER * 3.5,
+            }),
+            keep_distance,
+        )
+}
+
+// Like the melee phase, but charges at the player whenever the dash is ready
+fn lab_boss_frenzy(target: TargetRole) -> StateMachine {
+    lab_boss_melee(target)
+        .trans::<ApproachAndKeepDistance>(
+            CanDash {
+                target,
+                range: PIXELS_PER_METER * 8.0,
+            },
+            Dash::new(target, PIXELS_PER_METER * 18.0),
+        )
+        .trans::<Dash>(
+            DashFinished,
+            ApproachAndKeepDistance {
+                target,
+                inner_distance: PIXELS_PER_METER * 3.5,
+                outer_distance: PIXELS_PER_METER * 5.0,
+            },
+        )
+}
@@ -1,13 +1,15 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::{
     prelude::{FieldValue, LdtkEntityAppExt},
-    EntityInstance, LdtkEntity,
+    EntityInstance, LdtkAsset, LdtkEntity,
 };
 use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, RapierContext};
 
 use crate::{
+    app_state::loading::LevelAssets,
     game::{
-        level_manager::{LevelManager, LevelObject},
+        boss_encounter::BossEncounter,
+        level_manager::{level_identifiers, LevelManager, LevelObject},
         GameState,
     },
     PIXELS_PER_METER,
@@ -22,8 +24,14 @@ pub struct LevelExitEntityBundle {
     pub level_end: LevelExitBundle,
 }
 
+// `destination` is the LDtk identifier of the level this exit leads to. Exits without one lead to
+// the next level in the project, and `ENDGAME_DESTINATION` ends the run.
 #[derive(Component, Clone, Default, Debug)]
-pub struct LevelExit;
+pub struct LevelExit {
+    pub destination: Option<String>,
+    pub label: Option<String>,
+    pub bonus_offers: usize,
+}
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
 pub struct LevelExitBundle {
@@ -50,17 +58,37 @@ fn find_field_value<'a>(
 impl From<&EntityInstance> for LevelExitBundle {
     fn from(entity_instance: &EntityInstance) -> LevelExitBundle {
         // Helper closure to get field value and simplify error handling
-        let _get_field_value = |field_identifier: &str| {
+        let get_field_value = |field_identifier: &str| {
             find_field_value(entity_instance, field_identifier)
                 .ok_or_else(|| format!("Missing field: {}", field_identifier))
         };
 
+        // All fields are optional, an exit without any of them behaves like the old linear exit
+        let destination = match get_field_value("destination") {
+            Ok(FieldValue::String(Some(destination))) if !destination.is_empty() => {
+                Some(destination.clone())
+            }
+            _ => None,
+        };
+        let label = match get_field_value("label") {
+            Ok(FieldValue::String(Some(label))) if !label.is_empty() => Some(label.clone()),
+            _ => None,
+        };
+        let bonus_offers = match get_field_value("bonus_offers") {
+            Ok(FieldValue::Int(Some(bonus_offers))) => (*bonus_offers).max(0) as usize,
+            _ => 0,
+        };
+
         let level_start_position = entity_instance.grid.as_vec2();
 
         LevelExitBundle {
             transform: Transform::from_xyz(level_start_position.x, level_start_position.y, 0.0),
             global_transform: GlobalTransform::default(),
-            level_start: LevelExit,
+            level_start: LevelExit {
+                destination,
+                label,
+                bonus_offers,
+            },
             level_object: LevelObject,
             collider: Collider::cuboid(PIXELS_PER_METER * 2.1, PIXELS_PER_METER * 2.1),
             collision_types: ActiveCollisionTypes::STATIC_STATIC,
@@ -74,31 +102,106 @@ impl Plugin for LevelExitPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.register_ldtk_entity::<LevelExitEntityBundle>("LevelExit");
 
-        app.add_system(level_end_system.in_set(OnUpdate(GameState::LevelComplete)));
+        app.add_system(level_end_system.in_set(OnUpdate(GameState::LevelComplete)))
+            .add_system(level_exit_labels.in_schedule(OnEnter(GameState::LevelComplete)));
     }
 }
 
-// Move the player to the next level if this level is over
+#[derive(Component)]
+pub struct LevelExitLabel;
+
+// Once the level is cleared, show what each exit leads to above it
+pub fn level_exit_labels(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<(Entity, &LevelExit)>,
+    level_manager: Option<Res<LevelManager>>,
+    level_assets: Res<LevelAssets>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    let level_manager = match level_manager {
+        Some(level_manager) => level_manager,
+        None => return,
+    };
+    // Exits only open once the level's boss is dead
+    if encounter_query
+        .iter()
+        .any(|encounter| encounter.stage.blocks_exits())
+    {
+        return;
+    }
+
+    let level_identifiers = ldtk_assets
+        .get(&level_assets.ldtk)
+        .map(level_identifiers)
+        .unwrap_or_default();
+
+    for (entity, level_exit) in query.iter() {
+        let destination = level_manager.resolve_destination(level_exit, &level_identifiers);
+        let mut text = match (&level_exit.label, &destination) {
+            (Some(label), _) => label.clone(),
+            (None, Some(destination)) => destination.clone(),
+            (None, None) => "The End".to_string(),
+        };
+        if level_exit.bonus_offers > 0 {
+            text.push_str(&format!("\n+{} mutations", level_exit.bonus_offers));
+        }
+
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        text,
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_xyz(0.0, PIXELS_PER_METER * 2.5, 10.0),
+                    ..default()
+                },
+                LevelExitLabel,
+            ));
+        });
+    }
+}
+
+// Move the player to the level behind whichever exit they walk into if this level is over
 pub fn level_end_system(
     rapier_context: Res<RapierContext>,
     query: Query<(Entity, &LevelExit, &Collider), Without<Player>>,
     mut player_query: Query<(Entity, &Collider), With<Player>>,
+    encounter_query: Query<&BossEncounter>,
     mut next_state: ResMut<NextState<GameState>>,
-    level_manager: Option<Res<LevelManager>>,
+    level_manager: Option<ResMut<LevelManager>>,
+    level_assets: Res<LevelAssets>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
 ) {
-    let level_manager = match level_manager {
+    let mut level_manager = match level_manager {
         Some(level_manager) => level_manager,
         None => return,
     };
+    let level_identifiers = ldtk_assets
+        .get(&level_assets.ldtk)
+        .map(level_identifiers)
+        .unwrap_or_default();
 
     for (player_entity, _) in player_query.iter_mut() {
-        for (level_end_entity, _, _) in query.iter() {
+        for (level_end_entity, level_exit, _) in query.iter() {
             if rapier_context.intersection_pair(player_entity, level_end_entity) == Some(true) {
-                if level_manager.current_level == 9 {
-                    next_state.set(GameState::EndgameCutscene);
-                } else {
-                    next_state.set(GameState::MutationSelection);
+                match level_manager.resolve_destination(level_exit, &level_identifiers) {
+                    Some(destination) => {
+                        level_manager.next_level = Some(destination);
+                        level_manager.bonus_offers = level_exit.bonus_offers;
+                        next_state.set(GameState::MutationSelection);
+                    }
+                    None => {
+                        next_state.set(GameState::EndgameCutscene);
+                    }
                 }
+                return;
             }
         }
     }
@@ -63,13 +63,14 @@ impl Plugin for LevelStartPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.register_ldtk_entity::<LevelStartEntityBundle>("LevelStart");
 
-        app.add_system(level_start_system.in_schedule(OnEnter(GameState::InLevel)));
+        app.add_system(level_start_system.in_set(OnUpdate(GameState::InLevel)));
     }
 }
 
-// Move the player to the start of the level
+// Move the player to the start of the level once it's spawned. Only a freshly set up level has a new
+// start, so going back to the arena after picking a mutation leaves the player where they were
 pub fn level_start_system(
-    query: Query<(&LevelStart, &Transform), Without<Player>>,
+    query: Query<(&LevelStart, &Transform), (Added<LevelStart>, Without<Player>)>,
     mut player_query: Query<&mut Transform, With<Player>>,
 ) {
     let player = player_query.get_single_mut();
@@ -4,14 +4,14 @@ use crate::{
         loading::{SfxAssets, SoundEffects, SpriteAssets},
         AppState,
     },
-    entity::creature::{Bleed, DealDamage},
-    game::mutation_manager::{MutationManager, MutationType},
+    game::mutation_manager::{MutationManager, MutationType, SynergyType},
     PIXELS_PER_METER,
 };
 use bevy::prelude::*;
+use bevy::utils::Duration;
 use bevy_kira_audio::AudioChannel;
 use bevy_kira_audio::AudioControl;
-use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, RapierContext, Sensor};
+use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, Sensor};
 use leafwing_input_manager::{
     prelude::{ActionState, InputManagerPlugin, InputMap, VirtualDPad},
     Actionlike, InputManagerBundle,
@@ -20,14 +20,28 @@ use rand::Rng;
 use seldom_state::prelude::InputTriggerPlugin;
 
 use super::{
-    creature::{Creature, CreatureBundle, Heal, Knockback, Lifetime, Velocity},
-    Enemy, ZSort,
+    creature::{Creature, CreatureBundle, Knockback, Lifetime, Velocity},
+    faction::{Faction, HurtboxKnockback},
+    projectile::{spawn_projectiles, Projectile, ProjectileAtlases},
+    ZSort,
 };
 
+// Holding Attack this long starts charging a heavy attack
+const HEAVY_CHARGE_DELAY: f32 = 0.2;
+
 #[derive(Component, Reflect)]
 pub struct Player {
     pub attack_timer: Timer,
     pub attack_cooldown: Timer,
+    pub current_attack: Option<PlayerAttack>,
+    // Which light attack of the chain comes next
+    pub combo_step: usize,
+    // How long after a light attack the next one still continues the chain
+    pub combo_window: Timer,
+    // Attack was pressed mid swing, the next hit goes off as soon as this one ends
+    pub attack_buffered: bool,
+    // How long Attack has been held, the heavy attack is ready once it finishes
+    pub charge_timer: Timer,
     pub roll_timer: Timer,
     pub roll_invulnerable_timer: Timer,
     pub roll_cooldown_timer: Timer,
@@ -40,6 +54,11 @@ impl Default for Player {
         Self {
             attack_timer: Timer::from_seconds(0.4, TimerMode::Once),
             attack_cooldown: Timer::from_seconds(0.6, TimerMode::Once),
+            current_attack: None,
+            combo_step: 0,
+            combo_window: Timer::from_seconds(0.35, TimerMode::Once),
+            attack_buffered: false,
+            charge_timer: Timer::from_seconds(0.8, TimerMode::Once),
             roll_timer: Timer::from_seconds(0.5, TimerMode::Once),
             roll_invulnerable_timer: Timer::from_seconds(0.35, TimerMode::Once),
             roll_cooldown_timer: Timer::from_seconds(2.5, TimerMode::Once),
@@ -86,6 +105,8 @@ pub enum PlayerAction {
     Move,
     Attack,
     Roll,
+    // Whatever active the player's mutations grant, see `AbilitySlot`
+    Ability,
 }
 
 impl Plugin for PlayerPlugin {
@@ -100,21 +121,108 @@ impl Plugin for PlayerPlugin {
             .add_system(player_attacking_state_system.in_set(OnUpdate(AppState::InGame)))
             .add_system(player_attacking_behavior_system.in_set(OnUpdate(AppState::InGame)))
             .add_system(player_animation_system.in_set(OnUpdate(AppState::InGame)))
-            .add_system(player_damage_system.in_set(OnUpdate(AppState::InGame)))
             .add_system(player_check_mutation.in_set(OnUpdate(AppState::InGame)));
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Reflect)]
+#[derive(Clone, Copy, PartialEq, Reflect, FromReflect)]
 pub enum PlayerAnimationState {
     Idle,
     Rolling,
-    Attacking,
+    Attacking(PlayerAttack),
+    // Winding up a heavy attack
+    Charging,
+}
+
+// Light attacks chain Light1 -> Light2 -> Light3 when Attack is pressed again in time, holding
+// Attack charges up the heavy one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect, FromReflect)]
+pub enum PlayerAttack {
+    Light1,
+    Light2,
+    Light3,
+    Heavy,
 }
 
+impl PlayerAttack {
+    const LIGHT_CHAIN: [PlayerAttack; 3] = [
+        PlayerAttack::Light1,
+        PlayerAttack::Light2,
+        PlayerAttack::Light3,
+    ];
+
+    fn duration(&self) -> f32 {
+        match self {
+            PlayerAttack::Light1 | PlayerAttack::Light2 => 0.3,
+            PlayerAttack::Light3 => 0.4,
+            PlayerAttack::Heavy => 0.45,
+        }
+    }
+
+    fn damage(&self) -> f32 {
+        match self {
+            PlayerAttack::Light1 => 20.0,
+            PlayerAttack::Light2 => 22.0,
+            PlayerAttack::Light3 => 30.0,
+            PlayerAttack::Heavy => 60.0,
+        }
+    }
+
+    fn knockback(&self) -> f32 {
+        match self {
+            PlayerAttack::Light1 => 250.0,
+            PlayerAttack::Light2 => 200.0,
+            PlayerAttack::Light3 => 500.0,
+            PlayerAttack::Heavy => 700.0,
+        }
+    }
+
+    // The finisher and the heavy attack end the chain and put attacking on cooldown
+    fn ends_chain(&self) -> bool {
+        matches!(self, PlayerAttack::Light3 | PlayerAttack::Heavy)
+    }
+
+    // Hurtbox shape and where it sits relative to the player, `facing` is 1 or -1
+    fn hurtbox(&self, facing: f32) -> (Collider, Vec2) {
+        match self {
+            // The lariat, wide and flat
+            PlayerAttack::Light1 => (
+                Collider::cuboid(PIXELS_PER_METER * 2.5, PIXELS_PER_METER * 1.0),
+                Vec2::new(0.0, PIXELS_PER_METER * 0.5),
+            ),
+            // A backhand out in front
+            PlayerAttack::Light2 => (
+                Collider::cuboid(PIXELS_PER_METER * 1.5, PIXELS_PER_METER * 1.5),
+                Vec2::new(facing * PIXELS_PER_METER * 1.25, PIXELS_PER_METER * 0.5),
+            ),
+            PlayerAttack::Light3 => (
+                Collider::ball(PIXELS_PER_METER * 2.0),
+                Vec2::new(0.0, PIXELS_PER_METER * 0.5),
+            ),
+            PlayerAttack::Heavy => (
+                Collider::ball(PIXELS_PER_METER * 2.75),
+                Vec2::new(0.0, PIXELS_PER_METER * 0.5),
+            ),
+        }
+    }
+}
+
+// Winding up a heavy attack, see `Player::charge_timer`
+#[derive(Default, Component)]
+pub struct Charging;
+
 pub fn player_attacking_state_system(
     time: Res<Time>,
-    mut player_info: Query<(Entity, &mut Player, &ActionState<PlayerAction>)>,
+    mut player_info: Query<
+        (
+            Entity,
+            &mut Player,
+            &Transform,
+            &ActionState<PlayerAction>,
+            Option<&Charging>,
+        ),
+        Without<Dead>,
+    >,
     hurtbox_query: Query<(Entity, &PlayerHurtboxDamage), Without<Lifetime>>,
     attacking_query: Query<&Attacking>,
     rolling_query: Query<&Rolling>,
@@ -122,35 +230,122 @@ pub fn player_attacking_state_system(
     sfx: Res<AudioChannel<SoundEffects>>,
     music_assets: Res<SfxAssets>,
     mut mutation_manager: ResMut<MutationManager>,
+    last_facing: Res<LastFacing>,
+    projectile_atlases: Res<ProjectileAtlases>,
 ) {
-    for (entity, mut player, action_state) in player_info.iter_mut() {
+    for (entity, mut player, transform, action_state, charging) in player_info.iter_mut() {
         let is_rolling = rolling_query.get(entity).is_ok();
-        let is_attacking = attacking_query.get(entity).is_ok();
+        let mut is_attacking = attacking_query.get(entity).is_ok();
+        let cyclone = mutation_manager.has_mutation(MutationType::Cyclone);
 
         player.attack_cooldown.tick(time.delta());
 
         let mut action_state_active = action_state.just_pressed(PlayerAction::Attack);
         // If cyclone mutation, tick the cooldown twice as fast, and allow the player to hold the button to spin
-        if mutation_manager.has_mutation(MutationType::Cyclone) {
+        if cyclone {
             player.attack_cooldown.tick(time.delta());
             action_state_active = action_state.pressed(PlayerAction::Attack);
         }
 
+        // If attacking, tick the attack timer
+        if is_attacking {
+            player.attack_timer.tick(time.delta());
+
+            // Pressing again mid swing queues up the next hit of the chain
+            if action_state.just_pressed(PlayerAction::Attack) {
+                player.attack_buffered = true;
+            }
+        }
+        // If the attack_timer is finished, remove the Attacking and PlayerHurtbox components from the player
+        if player.attack_timer.finished() && is_attacking {
+            commands.entity(entity).remove::<Attacking>();
+            hurtbox_query.iter().for_each(|(hurtbox_entity, _)| {
+                commands.entity(hurtbox_entity).despawn_recursive();
+            });
+
+            if player
+                .current_attack
+                .map_or(true, |attack| attack.ends_chain())
+            {
+                player.combo_step = 0;
+                player.attack_buffered = false;
+                player.attack_cooldown.reset();
+            } else {
+                player.combo_window.reset();
+            }
+            player.current_attack = None;
+            player.attack_timer.reset();
+            is_attacking = false;
+        }
+
+        // The chain drops if the next hit doesn't come in time
+        if !is_attacking
+            && player.combo_step > 0
+            && !player.attack_buffered
+            && player.combo_window.tick(time.delta()).finished()
+        {
+            player.combo_step = 0;
+        }
+
+        // Holding Attack while not swinging charges the heavy attack, letting go once it's full
+        // unleashes it. Cyclone spins on hold instead
+        let mut heavy = false;
+        let mut tapped = false;
+        if !cyclone && !is_attacking && !is_rolling {
+            if action_state.pressed(PlayerAction::Attack) {
+                player.charge_timer.tick(time.delta());
+                if charging.is_none() && player.charge_timer.elapsed_secs() >= HEAVY_CHARGE_DELAY {
+                    commands.entity(entity).insert(Charging);
+                }
+            } else if charging.is_some() {
+                heavy = player.charge_timer.finished();
+                commands.entity(entity).remove::<Charging>();
+                player.charge_timer.reset();
+            } else {
+                // Let go before the charge started, so it was a tap
+                tapped = action_state.just_released(PlayerAction::Attack)
+                    && player.charge_timer.elapsed_secs() > 0.0;
+                player.charge_timer.reset();
+            }
+        }
+
+        // Light attacks go off on release so holding Attack goes straight into the charge. Rolls
+        // can't charge and cyclone spins on hold, so those still swing on press
+        let pressed = if cyclone || is_rolling {
+            action_state_active
+        } else {
+            tapped
+        };
+        let light = (pressed || player.attack_buffered) && charging.is_none();
+
         // If the attack cooldown is finished, and the attack button is just pressed, and the player is not rolling, start attacking
         // If the player has the bowling ball mutation, allow them to attack while rolling
-        if (player.attack_cooldown.finished()
-            && action_state_active
-            && !is_rolling
-            && !is_attacking)
-            || (mutation_manager.has_mutation(MutationType::BowlingBall)
-                && action_state_active
-                && is_rolling
-                && !is_attacking)
+        if (light || heavy)
+            && !is_attacking
+            && ((player.attack_cooldown.finished() && !is_rolling)
+                || (mutation_manager.has_mutation(MutationType::BowlingBall) && is_rolling))
         {
-            let mut damage = 20;
-            if mutation_manager.has_mutation(MutationType::HeavyBones) {
-                damage = (damage as f32 * 1.2) as u32;
+            let attack = if heavy {
+                PlayerAttack::Heavy
+            } else {
+                PlayerAttack::LIGHT_CHAIN[player.combo_step % PlayerAttack::LIGHT_CHAIN.len()]
+            };
+            if !heavy {
+                player.combo_step += 1;
             }
+            player.attack_buffered = false;
+            player.charge_timer.reset();
+            player.current_attack = Some(attack);
+
+            let facing = match last_facing.facing {
+                Facing::Left => -1.0,
+                Facing::Right => 1.0,
+            };
+            let (collider, offset) = attack.hurtbox(facing);
+
+            // HeavyBones adds 20% per rank
+            let heavy_bones = mutation_manager.mutation_rank(MutationType::HeavyBones);
+            let damage = (attack.damage() * (1.0 + 0.2 * heavy_bones as f32)) as u32;
 
             sfx.play(music_assets.lariat.clone()).with_volume(0.35);
             commands
@@ -159,33 +354,45 @@ pub fn player_attacking_state_system(
                 .with_children(|parent| {
                     parent.spawn((
                         PlayerHurtbox {
-                            collider: Collider::cuboid(
-                                PIXELS_PER_METER * 2.5,
-                                PIXELS_PER_METER * 1.0,
-                            ),
-                            damage: PlayerHurtboxDamage(20),
+                            collider,
+                            damage: PlayerHurtboxDamage(damage),
                             sensor: Sensor,
-                            transform: Transform::from_xyz(0.0, PIXELS_PER_METER * 0.5, 0.0),
+                            transform: Transform::from_translation(offset.extend(0.0)),
                             ..default()
                         },
+                        HurtboxKnockback(attack.knockback()),
                         ActiveCollisionTypes::STATIC_STATIC,
                     ));
                 });
-            player.attack_cooldown.reset();
-            player.attack_timer.reset();
-        }
 
-        // If attacking, tick the attack timer
-        if is_attacking {
-            player.attack_timer.tick(time.delta());
-        }
-        // If the attack_timer is finished, remove the Attacking and PlayerHurtbox components from the player and reset the cooldown timer
-        if player.attack_timer.finished() && is_attacking {
-            commands.entity(entity).remove::<Attacking>();
-            hurtbox_query.iter().for_each(|(hurtbox_entity, _)| {
-                commands.entity(hurtbox_entity).despawn_recursive();
-            });
+            // Lasers shoot out the way the player is facing with every swing, and behind them
+            // too from rank 2
+            let lasers = mutation_manager.mutation_rank(MutationType::Lasers);
+            if lasers > 0 {
+                let direction = match last_facing.facing {
+                    Facing::Left => Vec2::NEG_X,
+                    Facing::Right => Vec2::X,
+                };
+                let directions = if lasers >= 2 {
+                    vec![direction, -direction]
+                } else {
+                    vec![direction]
+                };
+                for direction in directions {
+                    spawn_projectiles(
+                        &mut commands,
+                        &projectile_atlases,
+                        Projectile::PlayerLaser,
+                        Faction::Player,
+                        transform.translation,
+                        direction,
+                    );
+                }
+            }
 
+            player
+                .attack_timer
+                .set_duration(Duration::from_secs_f32(attack.duration()));
             player.attack_timer.reset();
         }
     }
@@ -199,6 +406,7 @@ pub fn player_attacking_behavior_system(
 fn player_animation_system(
     rolling_query: Query<&Rolling>,
     attacking_query: Query<&Attacking>,
+    charging_query: Query<&Charging>,
     mut player_info: Query<(
         Entity,
         &mut Player,
@@ -208,19 +416,19 @@ fn player_animation_system(
     )>,
     _commands: Commands,
 ) {
-    for (entity, mut player, mut animated, mut collider, _texture_atlas_sprite) in
+    for (entity, mut player, mut animated, mut collider, mut texture_atlas_sprite) in
         player_info.iter_mut()
     {
         let is_rolling = rolling_query.get(entity).is_ok();
         let is_attacking = attacking_query.get(entity).is_ok();
+        let is_charging = charging_query.get(entity).is_ok();
 
         // Determine the player's current animation state based on their rolling and attacking status
-        let current_animation_state = if is_attacking {
-            PlayerAnimationState::Attacking
-        } else if is_rolling {
-            PlayerAnimationState::Rolling
-        } else {
-            PlayerAnimationState::Idle
+        let current_animation_state = match player.current_attack {
+            Some(attack) if is_attacking => PlayerAnimationState::Attacking(attack),
+            _ if is_rolling => PlayerAnimationState::Rolling,
+            _ if is_charging => PlayerAnimationState::Charging,
+            _ => PlayerAnimationState::Idle,
         };
 
         // Check if the animation state has changed
@@ -232,7 +440,9 @@ fn player_animation_system(
             let state_animated = player.animation_state.animated();
             let state_collider = player.animation_state.collider();
 
-            // Update the player's Animated, Collider, and TextureAtlasSprite components
+            // Update the player's Animated, Collider, and TextureAtlasSprite components.
+            // Chained attacks start on their own first frame instead of wherever the last left off
+            texture_atlas_sprite.index = state_animated.first;
             *animated = state_animated;
             *collider = state_collider;
         }
@@ -248,10 +458,36 @@ impl PlayerAnimationState {
                 last: 0,
                 ..default()
             },
-            PlayerAnimationState::Attacking => Animated {
+            // The sheet only has the two lariat frames, each hit of the chain plays them
+            // differently
+            PlayerAnimationState::Attacking(PlayerAttack::Light1) => Animated {
+                timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+                first: 2,
+                last: 3,
+                ..default()
+            },
+            PlayerAnimationState::Attacking(PlayerAttack::Light2) => Animated {
+                timer: Timer::from_seconds(0.15, TimerMode::Repeating),
+                first: 3,
+                last: 3,
+                ..default()
+            },
+            PlayerAnimationState::Attacking(PlayerAttack::Light3) => Animated {
+                timer: Timer::from_seconds(0.05, TimerMode::Repeating),
+                first: 2,
+                last: 3,
+                ..default()
+            },
+            PlayerAnimationState::Attacking(PlayerAttack::Heavy) => Animated {
+                timer: Timer::from_seconds(0.075, TimerMode::Repeating),
+                first: 2,
+                last: 3,
+                ..default()
+            },
+            PlayerAnimationState::Charging => Animated {
                 timer: Timer::from_seconds(0.1, TimerMode::Repeating),
-                first: 2, // Replace with the first frame of the attacking animation
-                last: 3,  // Replace with the last frame of the attacking animation
+                first: 2,
+                last: 2,
                 ..default()
             },
             PlayerAnimationState::Rolling => Animated {
@@ -267,7 +503,9 @@ impl PlayerAnimationState {
         match self {
             PlayerAnimationState::Idle => Collider::ball(PIXELS_PER_METER * 1.0),
             PlayerAnimationState::Rolling => Collider::ball(PIXELS_PER_METER * 0.5),
-            PlayerAnimationState::Attacking => Collider::ball(PIXELS_PER_METER * 1.0),
+            PlayerAnimationState::Attacking(_) | PlayerAnimationState::Charging => {
+                Collider::ball(PIXELS_PER_METER * 1.0)
+            }
         }
     }
 }
@@ -283,6 +521,10 @@ pub struct Attacking;
 #[derive(Default, Component)]
 pub struct Immune;
 
+// The player's health ran out, input is ignored until the run is retried
+#[derive(Default, Component)]
+pub struct Dead;
+
 #[derive(Default)]
 pub enum Facing {
     #[default]
@@ -306,7 +548,7 @@ pub fn player_movement_system(
             &ActionState<PlayerAction>,
             Option<&Knockback>,
         ),
-        With<Player>,
+        (With<Player>, Without<Dead>),
     >,
     mut next_state: ResMut<NextState<AppState>>,
     rolling_query: Query<&Rolling>,
@@ -369,7 +611,10 @@ pub fn player_movement_system(
 
 pub fn player_rolling_state_system(
     time: Res<Time>,
-    mut player_info: Query<(Entity, &mut Player, &Velocity, &ActionState<PlayerAction>)>,
+    mut player_info: Query<
+        (Entity, &mut Player, &Velocity, &ActionState<PlayerAction>),
+        Without<Dead>,
+    >,
     rolling_query: Query<&Rolling>,
     attacking_query: Query<&Attacking>,
     immune_query: Query<&Immune>,
@@ -383,6 +628,9 @@ pub fn player_rolling_state_system(
         let is_attacking = attacking_query.get(entity).is_ok();
 
         player.roll_cooldown_timer.tick(time.delta());
+        if mutation_manager.has_synergy(SynergyType::Pinball) {
+            player.roll_cooldown_timer.tick(time.delta());
+        }
         // If the roll cooldown is finished, and the roll button is just pressed, and the player is not rolling, start rolling and set the entity as immune
         if player.roll_cooldown_timer.finished()
             && action_state.just_pressed(PlayerAction::Roll)
@@ -401,12 +649,20 @@ pub fn player_rolling_state_system(
             player.roll_timer.reset();
             player.roll_invulnerable_timer.reset();
             player.roll_cooldown_timer.reset();
+            // Rolling drops a charging heavy attack
+            commands.entity(entity).remove::<Charging>();
+            player.charge_timer.reset();
             // If player has bowling ball mutation, don't despawn the hurtbox
             if is_attacking && !mutation_manager.has_mutation(MutationType::BowlingBall) {
                 commands.entity(entity).remove::<Attacking>();
                 hurtbox_query.iter().for_each(|hurtbox_entity| {
                     commands.entity(hurtbox_entity).despawn_recursive();
                 });
+                // and breaks the chain
+                player.current_attack = None;
+                player.combo_step = 0;
+                player.attack_buffered = false;
+                player.attack_timer.reset();
             }
         }
 
@@ -515,6 +771,10 @@ pub fn spawn_player(
                 .insert(KeyCode::E, PlayerAction::Roll)
                 .insert(GamepadButtonType::East, PlayerAction::Roll)
                 .insert(GamepadButtonType::North, PlayerAction::Roll)
+                .insert(KeyCode::C, PlayerAction::Ability)
+                .insert(KeyCode::I, PlayerAction::Ability)
+                .insert(KeyCode::F, PlayerAction::Ability)
+                .insert(GamepadButtonType::RightTrigger, PlayerAction::Ability)
                 .build(),
             ..default()
         },
@@ -522,58 +782,6 @@ pub fn spawn_player(
 }
 
 // bad name
-fn player_damage_system(
-    rapier_context: Res<RapierContext>,
-    mut commands: Commands,
-    mut enemy_hitbox_query: Query<(Entity, &Transform, &mut Creature, &Collider), With<Enemy>>,
-    mut player_hurtbox_query: Query<(Entity, &GlobalTransform, &Collider, &PlayerHurtboxDamage)>,
-    player_query: Query<Entity, With<Player>>,
-    mutation_manager: Res<MutationManager>,
-) {
-    for (enemy_hitbox_entity, enemy_transform, _enemy_creature, _enemy_collider) in
-        enemy_hitbox_query.iter_mut()
-    {
-        for (player_hurtbox_entity, player_transform, _player_collider, player_hurtbox_damage) in
-            player_hurtbox_query.iter_mut()
-        {
-            if rapier_context.intersection_pair(player_hurtbox_entity, enemy_hitbox_entity)
-                == Some(true)
-            {
-                let player_entity = player_query.get_single();
-
-                commands.entity(enemy_hitbox_entity).insert(DealDamage {
-                    amount: player_hurtbox_damage.0 as f32,
-                    knockback_direction: (enemy_transform.translation.truncate()
-                        - player_transform.translation().truncate())
-                    .normalize_or_zero(),
-                    knockback_force: 250.0,
-                });
-
-                if mutation_manager.has_mutation(MutationType::Hemophilia) {
-                    let damage = (player_hurtbox_damage.0 as f32 / 10.0).ceil() as u32;
-
-                    commands.entity(enemy_hitbox_entity).insert(Bleed {
-                        damage: damage as f32,
-                        ticks: 3,
-                        tick_timer: Timer::from_seconds(1.5, TimerMode::Once),
-                    });
-                }
-
-                if let Ok(player_entity) = player_entity {
-                    // If the player has Vampirism
-                    if mutation_manager.has_mutation(MutationType::Vampirism) {
-                        commands.entity(player_entity).insert(Heal {
-                            amount: 1.0,
-                            ticks: 3,
-                            tick_timer: Timer::from_seconds(1.0, TimerMode::Once),
-                        });
-                    }
-                }
-            }
-        }
-    }
-}
-
 #[derive(Default, Component)]
 pub struct Rage {
     pub timer: Timer,
@@ -595,6 +803,18 @@ pub fn player_check_mutation(
     for (player_entity, mut transform, _player, _creature, shrink, grow, shrinkgrow) in
         player_query.iter_mut()
     {
+        // Mutations can be removed at the shop, start over from normal size and let whatever is
+        // left get applied again
+        if (grow.is_some() && !mutation_manager.has_mutation(MutationType::Grow))
+            || (shrink.is_some() && !mutation_manager.has_mutation(MutationType::Shrink))
+        {
+            commands
+                .entity(player_entity)
+                .remove::<(Grow, Shrink, ShrinkGrow)>();
+            transform.scale = Vec3::ONE;
+            continue;
+        }
+
         if mutation_manager.has_mutation(MutationType::Grow) && grow.is_none() {
             commands.entity(player_entity).insert(Grow);
             transform.scale = Vec3::splat(1.25);
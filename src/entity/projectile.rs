@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, RapierContext, Sensor};
+
+use crate::{
+    app_state::{loading::SpriteAssets, AppState},
+    behaviour::{
+        navigation::line_of_sight,
+        target::{TargetResolver, TargetRole},
+    },
+    game::{
+        level_manager::Wall,
+        mutation_manager::{MutationManager, MutationType},
+        GameState,
+    },
+    PIXELS_PER_METER,
+};
+
+use super::{
+    creature::{Bleed, Creature, Lifetime, Velocity},
+    faction::Faction,
+    player::{Immune, Player, PlayerHurtboxDamage},
+    telegraph::telegraph_projectile,
+    EnemyHurtboxDamage,
+};
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProjectileAtlases>()
+            .add_system(projectile_atlases_setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(projectile_motion_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(projectile_hit_system.in_set(OnUpdate(AppState::InGame)))
+            .add_system(projectile_reflect_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(ranged_attack_system.in_set(OnUpdate(GameState::InLevel)));
+    }
+}
+
+// Every kind of projectile in the game, the numbers for each live in `Projectile::definition`
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Projectile {
+    MutantProjectile,
+    AdeptBolt,
+    LabBossSludge,
+    SorcerianOrb,
+    PlayerLaser,
+}
+
+impl Projectile {
+    pub const ALL: [Projectile; 5] = [
+        Projectile::MutantProjectile,
+        Projectile::AdeptBolt,
+        Projectile::LabBossSludge,
+        Projectile::SorcerianOrb,
+        Projectile::PlayerLaser,
+    ];
+
+    pub fn definition(&self) -> ProjectileDefinition {
+        // There's only the one projectile frame in the mutant sheet, the rest are tinted copies
+        let sprite = ProjectileSprite {
+            sheet: |sprites| sprites.mutant.clone(),
+            tile_size: Vec2::new(64.0, 64.0),
+            columns: 4,
+            index: 3,
+            color: Color::WHITE,
+            scale: 1.0,
+        };
+
+        match self {
+            Projectile::MutantProjectile => ProjectileDefinition {
+                sprite,
+                radius: PIXELS_PER_METER * 0.25,
+                speed: 120.0,
+                acceleration: 0.0,
+                homing: 0.0,
+                pierce: 0,
+                lifetime: 3.0,
+                damage: 4,
+                count: 1,
+                spread: 0.0,
+                wind_up: 0.5,
+                on_hit: None,
+            },
+            // Starts slow and speeds up while curving towards the target
+            Projectile::AdeptBolt => ProjectileDefinition {
+                sprite: ProjectileSprite {
+                    color: Color::rgb(0.7, 0.3, 1.0),
+                    scale: 0.75,
+                    ..sprite
+                },
+                radius: PIXELS_PER_METER * 0.2,
+                speed: 60.0,
+                acceleration: 150.0,
+                homing: 1.5,
+                pierce: 0,
+                lifetime: 4.0,
+                damage: 5,
+                count: 1,
+                spread: 0.0,
+                wind_up: 0.6,
+                on_hit: None,
+            },
+            // A spray of goo that slows down and makes the player bleed
+            Projectile::LabBossSludge => ProjectileDefinition {
+                sprite: ProjectileSprite {
+                    color: Color::rgb(0.4, 1.0, 0.3),
+                    scale: 1.25,
+                    ..sprite
+                },
+                radius: PIXELS_PER_METER * 0.3,
+                speed: 180.0,
+                acceleration: -60.0,
+                homing: 0.0,
+                pierce: 0,
+                lifetime: 2.5,
+                damage: 6,
+                count: 5,
+                spread: 0.8,
+                wind_up: 0.8,
+                on_hit: Some(OnHitStatus::Bleed {
+                    damage: 1.0,
+                    ticks: 3,
+                }),
+            },
+            Projectile::SorcerianOrb => ProjectileDefinition {
+                sprite: ProjectileSprite {
+                    color: Color::rgb(0.3, 0.6, 1.0),
+                    ..sprite
+                },
+                radius: PIXELS_PER_METER * 0.3,
+                speed: 100.0,
+                acceleration: 0.0,
+                homing: 0.8,
+                pierce: 0,
+                lifetime: 5.0,
+                damage: 8,
+                count: 3,
+                spread: 0.5,
+                wind_up: 0.7,
+                on_hit: None,
+            },
+            Projectile::PlayerLaser => ProjectileDefinition {
+                sprite: ProjectileSprite {
+                    color: Color::rgb(1.0, 0.2, 0.2),
+                    scale: 0.75,
+                    ..sprite
+                },
+                radius: PIXELS_PER_METER * 0.3,
+                speed: 600.0,
+                acceleration: 0.0,
+                homing: 0.0,
+                pierce: 2,
+                lifetime: 1.0,
+                damage: 10,
+                count: 1,
+                spread: 0.0,
+                wind_up: 0.0,
+                on_hit: None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ProjectileSprite {
+    pub sheet: fn(&SpriteAssets) -> Handle<Image>,
+    pub tile_size: Vec2,
+    pub columns: usize,
+    pub index: usize,
+    pub color: Color,
+    pub scale: f32,
+}
+
+#[derive(Clone, Copy)]
+pub struct ProjectileDefinition {
+    pub sprite: ProjectileSprite,
+    // Collider radius in world pixels, regardless of the sprite's scale
+    pub radius: f32,
+    pub speed: f32,
+    // Pixels per second per second, negative values slow the projectile down
+    pub acceleration: f32,
+    // How fast the projectile turns towards its target, in radians per second
+    pub homing: f32,
+    // How many extra things the projectile goes through before it's used up
+    pub pierce: u32,
+    pub lifetime: f32,
+    pub damage: u32,
+    // Projectiles per shot, fanned out evenly over the spread angle in radians
+    pub count: u32,
+    pub spread: f32,
+    // How long enemies telegraph the shot before it's fired
+    pub wind_up: f32,
+    pub on_hit: Option<OnHitStatus>,
+}
+
+// Status applied to whatever the projectile hits, on top of the damage
+#[derive(Clone, Copy, Debug, Reflect, FromReflect)]
+pub enum OnHitStatus {
+    Bleed { damage: f32, ticks: u32 },
+}
+
+#[derive(Component, Reflect)]
+pub struct ProjectileMotion {
+    pub direction: Vec2,
+    pub speed: f32,
+    pub acceleration: f32,
+    pub homing: f32,
+    pub homing_target: TargetRole,
+}
+
+// What a projectile can hit is down to its `Faction`
+#[derive(Component, Reflect)]
+pub struct ProjectileImpact {
+    pub pierce: u32,
+    pub on_hit: Option<OnHitStatus>,
+    // Everything hit so far, so an overlap only counts once
+    pub hit: Vec<Entity>,
+}
+
+// Texture atlases for every projectile, made once instead of on every shot
+#[derive(Resource, Default)]
+pub struct ProjectileAtlases {
+    atlases: HashMap<Projectile, Handle<TextureAtlas>>,
+}
+
+pub fn projectile_atlases_setup(
+    mut projectile_atlases: ResMut<ProjectileAtlases>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    sprites: Res<SpriteAssets>,
+) {
+    for projectile in Projectile::ALL {
+        let sprite = projectile.definition().sprite;
+        let handle = texture_atlases.add(TextureAtlas::from_grid(
+            (sprite.sheet)(&sprites),
+            sprite.tile_size,
+            sprite.columns,
+            1,
+            None,
+            None,
+        ));
+        projectile_atlases.atlases.insert(projectile, handle);
+    }
+}
+
+// Fires one shot of the given projectile, which may be several projectiles if it has a spread
+pub fn spawn_projectiles(
+    commands: &mut Commands,
+    projectile_atlases: &ProjectileAtlases,
+    projectile: Projectile,
+    faction: Faction,
+    origin: Vec3,
+    direction: Vec2,
+) {
+    let definition = projectile.definition();
+    let texture_atlas = match projectile_atlases.atlases.get(&projectile) {
+        Some(texture_atlas) => texture_atlas.clone(),
+        None => return,
+    };
+    let direction = direction.normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return;
+    }
+    let homing_target = homing_target(faction);
+
+    for i in 0..definition.count {
+        let angle = if definition.count > 1 {
+            -definition.spread / 2.0 + definition.spread * i as f32 / (definition.count - 1) as f32
+        } else {
+            0.0
+        };
+        let direction = Vec2::from_angle(angle).rotate(direction);
+
+        let mut projectile_entity = commands.spawn((
+            SpriteSheetBundle {
+                texture_atlas: texture_atlas.clone(),
+                sprite: TextureAtlasSprite {
+                    index: definition.sprite.index,
+                    color: definition.sprite.color,
+                    ..default()
+                },
+                transform: Transform::from_translation(origin)
+                    .with_scale(Vec3::splat(definition.sprite.scale)),
+                ..default()
+            },
+            // Colliders get scaled along with the sprite
+            Collider::ball(definition.radius / definition.sprite.scale),
+            Sensor,
+            ActiveCollisionTypes::STATIC_STATIC,
+            Velocity {
+                value: direction * definition.speed,
+            },
+            ProjectileMotion {
+                direction,
+                speed: definition.speed,
+                acceleration: definition.acceleration,
+                homing: definition.homing,
+                homing_target,
+            },
+            faction,
+            ProjectileImpact {
+                pierce: definition.pierce,
+                on_hit: definition.on_hit,
+                hit: Vec::new(),
+            },
+            Lifetime {
+                timer: Timer::from_seconds(definition.lifetime, TimerMode::Once),
+            },
+            Name::new(format!("{:?}", projectile)),
+        ));
+
+        // The usual hit resolution deals the damage
+        match faction {
+            Faction::Player => {
+                projectile_entity.insert(PlayerHurtboxDamage(definition.damage));
+            }
+            Faction::Enemy | Faction::Neutral => {
+                projectile_entity.insert(EnemyHurtboxDamage(definition.damage));
+            }
+        }
+    }
+}
+
+fn homing_target(faction: Faction) -> TargetRole {
+    match faction {
+        Faction::Enemy => TargetRole::Player,
+        Faction::Player | Faction::Neutral => TargetRole::NearestEnemy,
+    }
+}
+
+pub fn projectile_motion_system(
+    time: Res<Time>,
+    resolver: Res<TargetResolver>,
+    target_query: Query<&Transform, Without<ProjectileMotion>>,
+    mut projectile_query: Query<(Entity, &Transform, &mut ProjectileMotion, &mut Velocity)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, transform, mut motion, mut velocity) in projectile_query.iter_mut() {
+        let position = transform.translation.truncate();
+
+        if motion.homing > 0.0 {
+            let target_position = resolver
+                .resolve(motion.homing_target, entity, position)
+                .and_then(|target| target_query.get(target).ok())
+                .map(|target_transform| target_transform.translation.truncate());
+            if let Some(target_position) = target_position {
+                let desired = (target_position - position).normalize_or_zero();
+                if desired != Vec2::ZERO {
+                    // Turn towards the target, but only so fast
+                    let max_turn = motion.homing * delta;
+                    let turn = motion
+                        .direction
+                        .angle_between(desired)
+                        .clamp(-max_turn, max_turn);
+                    motion.direction = Vec2::from_angle(turn).rotate(motion.direction);
+                }
+            }
+        }
+
+        motion.speed = (motion.speed + motion.acceleration * delta).max(0.0);
+        velocity.value = motion.direction * motion.speed;
+    }
+}
+
+// Counts hits for piercing, applies on hit statuses and stops projectiles at walls
+pub fn projectile_hit_system(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    mut projectile_query: Query<(Entity, &Faction, &mut ProjectileImpact)>,
+    victim_query: Query<(Option<&Faction>, Option<&Player>, Option<&Immune>), With<Creature>>,
+    wall_query: Query<(), With<Wall>>,
+) {
+    for (entity, faction, mut impact) in projectile_query.iter_mut() {
+        let mut used_up = false;
+        for (collider1, collider2, intersecting) in rapier_context.intersections_with(entity) {
+            if !intersecting {
+                continue;
+            }
+            let other = if collider1 == entity {
+                collider2
+            } else {
+                collider1
+            };
+
+            if wall_query.get(other).is_ok() {
+                used_up = true;
+                break;
+            }
+
+            let is_victim = match victim_query.get(other) {
+                Ok((victim_faction, player, immune)) => {
+                    immune.is_none()
+                        && faction.is_hostile_to(Faction::of_creature(victim_faction, player))
+                }
+                Err(_) => false,
+            };
+            if !is_victim || impact.hit.contains(&other) {
+                continue;
+            }
+            impact.hit.push(other);
+
+            match impact.on_hit {
+                Some(OnHitStatus::Bleed { damage, ticks }) => {
+                    commands.entity(other).insert(Bleed {
+                        damage,
+                        ticks,
+                        tick_timer: Timer::from_seconds(1.5, TimerMode::Once),
+                    });
+                }
+                None => {}
+            }
+
+            if impact.hit.len() as u32 > impact.pierce {
+                used_up = true;
+                break;
+            }
+        }
+
+        if used_up {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// With the Reflect mutation, swinging at enemy projectiles sends them back the way they came, and
+// they hurt whoever they hit on the way
+pub fn projectile_reflect_system(
+    rapier_context: Res<RapierContext>,
+    mutation_manager: Res<MutationManager>,
+    mut projectile_query: Query<(
+        Entity,
+        &mut Faction,
+        &mut ProjectileMotion,
+        &mut ProjectileImpact,
+        &mut TextureAtlasSprite,
+    )>,
+    melee_query: Query<Entity, (With<PlayerHurtboxDamage>, Without<ProjectileImpact>)>,
+) {
+    if !mutation_manager.has_mutation(MutationType::Reflect) {
+        return;
+    }
+
+    for (entity, mut faction, mut motion, mut impact, mut sprite) in projectile_query.iter_mut() {
+        if !faction.is_hostile_to(Faction::Player) {
+            continue;
+        }
+        if !melee_query
+            .iter()
+            .any(|melee| rapier_context.intersection_pair(entity, melee) == Some(true))
+        {
+            continue;
+        }
+
+        *faction = Faction::Player;
+        motion.direction = -motion.direction;
+        motion.homing_target = homing_target(Faction::Player);
+        impact.hit.clear();
+        sprite.color = Color::rgb(1.0, 0.9, 0.4);
+    }
+}
+
+// Fires at the player every so often when they're in range and in sight, after a telegraph
+#[derive(Component, Reflect, Clone)]
+pub struct RangedAttack {
+    pub projectile: Projectile,
+    pub range: f32,
+    pub cooldown: Timer,
+}
+
+impl RangedAttack {
+    pub fn new(projectile: Projectile, range: f32, cooldown_seconds: f32) -> Self {
+        Self {
+            projectile,
+            range,
+            cooldown: Timer::from_seconds(cooldown_seconds, TimerMode::Once),
+        }
+    }
+}
+
+pub fn ranged_attack_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    resolver: Res<TargetResolver>,
+    transforms: Query<&Transform>,
+    walls: Query<(), With<Wall>>,
+    mut attacker_query: Query<(Entity, &mut RangedAttack)>,
+) {
+    for (entity, mut ranged_attack) in attacker_query.iter_mut() {
+        ranged_attack.cooldown.tick(time.delta());
+        if !ranged_attack.cooldown.finished() {
+            continue;
+        }
+
+        let (position, target_position) =
+            match resolver.resolve_positions(TargetRole::Player, entity, &transforms) {
+                Some(positions) => positions,
+                None => continue,
+            };
+        if position.distance(target_position) > ranged_attack.range
+            || !line_of_sight(&rapier_context, &walls, position, target_position)
+        {
+            continue;
+        }
+
+        telegraph_projectile(
+            &mut commands,
+            entity,
+            ranged_attack.projectile,
+            target_position - position,
+        );
+        ranged_attack.cooldown.reset();
+    }
+}
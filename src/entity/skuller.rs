@@ -1,24 +1,13 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::{Collider, Sensor};
 
-use crate::{
-    animation::Animated,
-    behaviour::separation::{separation_system, Separation},
-};
+use crate::{animation::Animated, behaviour::separation::Separation};
 
 use super::{
     creature::{Creature, CreatureBundle, Hitbox, Velocity},
     Enemy, EnemyHurtboxDamage, ZSort,
 };
 
-pub struct SkullerPlugin;
-
-impl Plugin for SkullerPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_system(separation_system::<Skuller>);
-    }
-}
-
 #[derive(Component, Reflect, Default)]
 pub struct Skuller;
 
@@ -1,17 +1,30 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy_kira_audio::{AudioChannel, AudioControl};
 use bevy_rapier2d::prelude::{Collider, Sensor};
 use rand::Rng;
+use seldom_state::prelude::{NotTrigger, StateMachine};
 
 use crate::{
     animation::Animated,
     app_state::loading::{SfxAssets, SoundEffects},
-    behaviour::separation::separation_system,
+    behaviour::{
+        boss_phase::{BossPhase, BossPhases},
+        states::{
+            approach_and_keep_distance::ApproachAndKeepDistance, idle::Idle,
+            phase_transition::PhaseTransition, wander::Wander,
+        },
+        target::TargetRole,
+        triggers::Near,
+    },
     game::{level_manager::SpawnerBundle, GameState},
+    PIXELS_PER_METER,
 };
 
 use super::{
     creature::{Creature, CreatureBundle, Hitbox, Velocity},
+    projectile::{Projectile, RangedAttack},
     spawner::{EnemyType, Spawner},
     Enemy, EnemyHurtboxDamage, ZSort,
 };
@@ -20,8 +33,7 @@ pub struct SorcerianPlugin;
 
 impl Plugin for SorcerianPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(separation_system::<Sorcerian>)
-            .add_system(do_shit.in_set(OnUpdate(GameState::InLevel)));
+        app.add_system(do_shit.in_set(OnUpdate(GameState::InLevel)));
     }
 }
 
@@ -83,29 +95,48 @@ impl SorcerianBundle {
 
 pub fn do_shit(
     mut commands: Commands,
-    mut sorcerian_query: Query<(Entity, &Transform, &mut Velocity, &mut Sorcerian)>,
+    mut sorcerian_query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Velocity,
+            &mut Sorcerian,
+            Option<&BossPhases>,
+        ),
+        Without<PhaseTransition>,
+    >,
     time: Res<Time>,
     sfx: Res<AudioChannel<SoundEffects>>,
     music_assets: Res<SfxAssets>,
 ) {
-    for (_sorcerian_query, adept_transform, mut velocity, mut sorcerian) in
+    for (_sorcerian_query, adept_transform, mut velocity, mut sorcerian, phases) in
         sorcerian_query.iter_mut()
     {
+        // Summons faster and always dashes in the second phase, and stops summoning in the last
+        let (summon_seconds, dash_chance) = match phases.map(|phases| phases.current()) {
+            None | Some(0) => (5.0, 0.5),
+            Some(1) => (3.5, 1.0),
+            Some(_) => continue,
+        };
+        let summon_duration = Duration::from_secs_f32(summon_seconds);
+        if sorcerian.spawn_timer.duration() != summon_duration {
+            sorcerian.spawn_timer.set_duration(summon_duration);
+        }
+
         sorcerian.spawn_timer.tick(time.delta());
         if sorcerian.spawn_timer.finished() {
             let mut rng = rand::thread_rng();
             let random_number = rng.gen_range(1..8);
 
             // Play random laff
-            let fiftyfifty = rng.gen_range(0..1);
-            if fiftyfifty == 0 {
+            if rng.gen_bool(0.5) {
                 sfx.play(music_assets.laff1.clone());
             } else {
                 sfx.play(music_assets.laff2.clone());
             }
 
             // Sometimes dash away
-            if fiftyfifty == 0 {
+            if rng.gen_bool(dash_chance) {
                 let random_vec2 = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
                 velocity.value += random_vec2 * 1500.0;
             }
@@ -128,6 +159,7 @@ pub fn do_shit(
                         spawn_rate: random_number,
                         spawn_count: random_number,
                         enemy_type,
+                        elite_chance: 0.0,
                     },
                 },
                 *adept_transform,
@@ -137,3 +169,95 @@ pub fn do_shit(
         }
     }
 }
+
+// Summons and shoots orbs while keeping its distance, kites further away summoning more at half
+// health and gives up summoning to chase the player with a barrage of orbs at the end
+pub fn sorcerian_phases(target: TargetRole) -> BossPhases {
+    BossPhases::new(
+        target,
+        vec![
+            BossPhase {
+                health_threshold: 1.0,
+                state_machine: sorcerian_keep_distance,
+                ranged_attack: Some(RangedAttack::new(
+                    Projectile::SorcerianOrb,
+                    PIXELS_PER_METER * 15.0,
+                    3.0,
+                )),
+                speed_multiplier: 1.0,
+            },
+            BossPhase {
+                health_threshold: 0.5,
+                state_machine: sorcerian_kite,
+                ranged_attack: Some(RangedAttack::new(
+                    Projectile::SorcerianOrb,
+                    PIXELS_PER_METER * 18.0,
+                    2.0,
+                )),
+                speed_multiplier: 1.2,
+            },
+            BossPhase {
+                health_threshold: 0.2,
+                state_machine: sorcerian_chase,
+                ranged_attack: Some(RangedAttack::new(
+                    Projectile::SorcerianOrb,
+                    PIXELS_PER_METER * 12.0,
+                    1.0,
+                )),
+                speed_multiplier: 1.3,
+            },
+        ],
+        120.0,
+    )
+}
+
+fn sorcerian_graph(target: TargetRole, inner_distance: f32, outer_distance: f32) -> StateMachine {
+    let approach = ApproachAndKeepDistance {
+        target,
+        inner_distance,
+        outer_distance,
+    };
+
+    // The initial state is `Idle`
+    StateMachine::new(Idle)
+        .trans::<Idle>(
+            Near {
+                target,
+                range: PIXELS_PER_METER * 20.0,
+            },
+            approach.clone(),
+        )
+        .trans::<Idle>(
+            NotTrigger(Near {
+                target,
+                range: PIXELS_PER_METER * 20.0,
+            }),
+            Wander::default(),
+        )
+        .trans::<Wander>(
+            Near {
+                target,
+                range: PIXELS_PER_METER * 20.0,
+            },
+            approach,
+        )
+        .trans::<ApproachAndKeepDistance>(
+            NotTrigger(Near {
+                target,
+                range: PIXELS_PER_METER * 20.0,
+            }),
+            Idle,
+        )
+}
+
+fn sorcerian_keep_distance(target: TargetRole) -> StateMachine {
+    sorcerian_graph(target, PIXELS_PER_METER * 5.0, PIXELS_PER_METER * 8.0)
+}
+
+fn sorcerian_kite(target: TargetRole) -> StateMachine {
+    sorcerian_graph(target, PIXELS_PER_METER * 8.0, PIXELS_PER_METER * 12.0)
+}
+
+fn sorcerian_chase(target: TargetRole) -> StateMachine {
+    sorcerian_graph(target, PIXELS_PER_METER * 3.0, PIXELS_PER_METER * 5.0)
+}
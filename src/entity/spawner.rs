@@ -12,21 +12,26 @@ use crate::{
     behaviour::{
         states::{
             approach_and_keep_distance::ApproachAndKeepDistance,
-            attack::{AttackAndKeepDistance, LabBossAttack},
+            dash::{Dash, DashCooldown},
             fire_projectile::{FireProjectile, FireProjectileAndKeepDistance},
+            follow::Follow,
             idle::Idle,
             wander::Wander,
         },
-        triggers::Near,
+        target::TargetRole,
+        triggers::{AndTrigger, CanDash, DashFinished, LineOfSight, LostFollowTarget, Near},
     },
     PIXELS_PER_METER,
 };
 
 use super::adept::AdeptBundle;
 use super::creature::FacePlayer;
+use super::elite::roll_elite;
+use super::faction::Faction;
 use super::goblin::GoblinBundle;
-use super::lab_boss::LabBossBundle;
-use super::sorcerian::SorcerianBundle;
+use super::lab_boss::{lab_boss_phases, LabBossBundle};
+use super::projectile::Projectile;
+use super::sorcerian::{sorcerian_phases, SorcerianBundle};
 use super::{
     creature::DontSetFacing, mutant::MutantBundle, player::Player, skuller::SkullerBundle,
     slimer::SlimerBundle,
@@ -47,9 +52,15 @@ pub struct Spawner {
     pub spawn_rate: usize,
     pub spawn_count: usize,
     pub enemy_type: EnemyType,
+    // Chance for each spawned enemy to roll elite modifiers
+    pub elite_chance: f32,
 }
 
-#[derive(Reflect, Default, FromReflect, Clone, Debug)]
+// Which kind of enemy a spawned entity is, so kills can be tallied by type
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct EnemyKind(pub EnemyType);
+
+#[derive(Reflect, Default, FromReflect, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum EnemyType {
     #[default]
     Slimer,
@@ -80,17 +91,40 @@ impl FromStr for EnemyType {
     }
 }
 
+impl EnemyType {
+    // Bosses have their own phases and never roll elite modifiers
+    pub fn is_boss(&self) -> bool {
+        matches!(self, EnemyType::LabBoss | EnemyType::Sorcerian)
+    }
+
+    // The LDtk enum value for this enemy type
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            EnemyType::Slimer => "Slimer",
+            EnemyType::Mutant => "Mutant",
+            EnemyType::Goblin => "Goblin",
+            EnemyType::GoblinBrute => "GoblinBrute",
+            EnemyType::Adept => "Adept",
+            EnemyType::Skuller => "Skuller",
+            EnemyType::LabBoss => "LabBoss",
+            EnemyType::Sorcerian => "Sorcerian",
+        }
+    }
+}
+
 pub fn spawn_system(
     mut commands: Commands,
     sprites: Res<SpriteAssets>,
     time: Res<Time>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    mut query: Query<(Entity, &mut Spawner, &Transform)>,
+    mut query: Query<(Entity, &mut Spawner, &Transform, Option<&Faction>)>,
     player_query: Query<Entity, With<Player>>,
 ) {
-    let player = player_query.get_single();
-    if let Ok(player) = player {
-        for (entity, mut spawner, transform) in &mut query.iter_mut() {
+    let mut rng = rand::thread_rng();
+
+    // Enemies target the player by role, but there's no point spawning them with no player around
+    if player_query.get_single().is_ok() {
+        for (entity, mut spawner, transform, faction) in &mut query.iter_mut() {
             // If there's nothing left to spawn, destroy the spawner
             if spawner.spawn_count == 0 {
                 commands.entity(entity).despawn();
@@ -105,63 +139,75 @@ pub fn spawn_system(
                 }
 
                 for _ in 0..spawner.spawn_rate {
-                    match spawner.enemy_type {
+                    let enemy = match spawner.enemy_type {
                         EnemyType::Slimer => spawn_slimer(
                             &mut commands,
                             &sprites,
                             &mut texture_atlases,
                             *transform,
-                            player,
+                            TargetRole::Player,
                         ),
                         EnemyType::Mutant => spawn_mutant(
                             &mut commands,
                             &sprites,
                             &mut texture_atlases,
                             *transform,
-                            player,
+                            TargetRole::Player,
                         ),
                         EnemyType::Goblin => spawn_goblin(
                             &mut commands,
                             &sprites,
                             &mut texture_atlases,
                             *transform,
-                            player,
+                            TargetRole::Player,
                         ),
                         EnemyType::GoblinBrute => spawn_goblin_brute(
                             &mut commands,
                             &sprites,
                             &mut texture_atlases,
                             *transform,
-                            player,
+                            TargetRole::Player,
                         ),
                         EnemyType::Adept => spawn_adept(
                             &mut commands,
                             &sprites,
                             &mut texture_atlases,
                             *transform,
-                            player,
+                            TargetRole::Player,
                         ),
                         EnemyType::Skuller => spawn_skuller(
                             &mut commands,
                             &sprites,
                             &mut texture_atlases,
                             *transform,
-                            player,
+                            TargetRole::Player,
                         ),
                         EnemyType::LabBoss => spawn_lab_boss(
                             &mut commands,
                             &sprites,
                             &mut texture_atlases,
                             *transform,
-                            player,
+                            TargetRole::Player,
                         ),
                         EnemyType::Sorcerian => spawn_sorcerian(
                             &mut commands,
                             &sprites,
                             &mut texture_atlases,
                             *transform,
-                            player,
+                            TargetRole::Player,
                         ),
+                    };
+                    commands
+                        .entity(enemy)
+                        .insert(EnemyKind(spawner.enemy_type.clone()));
+
+                    // Spawners with a faction, e.g. friendly summons, pass it on
+                    if let Some(faction) = faction {
+                        commands.entity(enemy).insert(*faction);
+                    }
+
+                    if !spawner.enemy_type.is_boss() && rng.gen::<f32>() < spawner.elite_chance {
+                        roll_elite(&mut commands, enemy, &mut rng);
                     }
 
                     // Decrement the number of entities left to spawn
@@ -177,8 +223,8 @@ fn spawn_skuller(
     sprites: &SpriteAssets,
     texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
     transform: Transform,
-    target: Entity,
-) {
+    target: TargetRole,
+) -> Entity {
     // Spawn the entity
     let texture_atlas_handle = texture_atlases.add(TextureAtlas::from_grid(
         sprites.skuller.clone(),
@@ -194,7 +240,18 @@ fn spawn_skuller(
     let mut timer = Timer::from_seconds(0.15, TimerMode::Repeating);
     timer.tick(Duration::from_millis(rng.gen_range(0..=150)));
 
-    let _enemy_entity = commands
+    let follow_leader = Follow {
+        target: TargetRole::Leader,
+        distance: PIXELS_PER_METER * 2.0,
+        range: PIXELS_PER_METER * 12.0,
+    };
+    let follow_ally = Follow {
+        target: TargetRole::NearestAlly,
+        distance: PIXELS_PER_METER * 1.5,
+        range: PIXELS_PER_METER * 6.0,
+    };
+
+    commands
         .spawn(SkullerBundle::new(
             texture_atlas_handle,
             sprite_size,
@@ -223,6 +280,22 @@ fn spawn_skuller(
                         outer_distance: PIXELS_PER_METER * 8.0,
                     },
                 )
+                // With nothing to chase, stick with the adept that summoned them, or failing that
+                // whoever else is around
+                .trans::<Idle>(
+                    Near {
+                        target: TargetRole::Leader,
+                        range: PIXELS_PER_METER * 12.0,
+                    },
+                    follow_leader.clone(),
+                )
+                .trans::<Idle>(
+                    Near {
+                        target: TargetRole::NearestAlly,
+                        range: PIXELS_PER_METER * 6.0,
+                    },
+                    follow_ally.clone(),
+                )
                 .trans::<Idle>(
                     NotTrigger(Near {
                         target,
@@ -241,6 +314,32 @@ fn spawn_skuller(
                         outer_distance: PIXELS_PER_METER * 8.0,
                     },
                 )
+                .trans::<Wander>(
+                    Near {
+                        target: TargetRole::Leader,
+                        range: PIXELS_PER_METER * 12.0,
+                    },
+                    follow_leader,
+                )
+                .trans::<Wander>(
+                    Near {
+                        target: TargetRole::NearestAlly,
+                        range: PIXELS_PER_METER * 6.0,
+                    },
+                    follow_ally,
+                )
+                .trans::<Follow>(
+                    Near {
+                        target,
+                        range: PIXELS_PER_METER * 20.0,
+                    },
+                    ApproachAndKeepDistance {
+                        target,
+                        inner_distance: PIXELS_PER_METER * 1.0,
+                        outer_distance: PIXELS_PER_METER * 8.0,
+                    },
+                )
+                .trans::<Follow>(LostFollowTarget, Idle)
                 .trans::<ApproachAndKeepDistance>(
                     NotTrigger(Near {
                         target,
@@ -248,7 +347,8 @@ fn spawn_skuller(
                     }),
                     Idle,
                 ),
-        );
+        )
+        .id()
 }
 
 fn spawn_slimer(
@@ -256,8 +356,8 @@ fn spawn_slimer(
     sprites: &SpriteAssets,
     texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
     transform: Transform,
-    target: Entity,
-) {
+    target: TargetRole,
+) -> Entity {
     // Spawn the entity
     let texture_atlas_handle = texture_atlases.add(TextureAtlas::from_grid(
         sprites.slimer.clone(),
@@ -273,7 +373,7 @@ fn spawn_slimer(
     let mut timer = Timer::from_seconds(0.15, TimerMode::Repeating);
     timer.tick(Duration::from_millis(rng.gen_range(0..=150)));
 
-    let _enemy_entity = commands
+    commands
         .spawn(SlimerBundle::new(
             texture_atlas_handle,
             sprite_size,
@@ -349,7 +449,8 @@ fn spawn_slimer(
                         outer_distance: PIXELS_PER_METER * 6.0,
                     },
                 ),
-        );
+        )
+        .id()
 }
 
 fn spawn_mutant(
@@ -357,8 +458,8 @@ fn spawn_mutant(
     sprites: &SpriteAssets,
     texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
     transform: Transform,
-    target: Entity,
-) {
+    target: TargetRole,
+) -> Entity {
     // Spawn the entity
     let texture_atlas_handle = texture_atlases.add(TextureAtlas::from_grid(
         sprites.mutant.clone(),
@@ -374,7 +475,7 @@ fn spawn_mutant(
     let mut timer = Timer::from_seconds(0.40, TimerMode::Repeating);
     timer.tick(Duration::from_millis(rng.gen_range(0..=150)));
 
-    let _enemy_entity = commands
+    commands
         .spawn(MutantBundle::new(
             texture_atlas_handle,
             sprite_size,
@@ -403,11 +504,18 @@ fn spawn_mutant(
                         outer_distance: PIXELS_PER_METER * 6.0,
                     },
                 )
+                // Only start shooting when there's a clear shot
                 .trans::<ApproachAndKeepDistance>(
-                    NotTrigger(Near {
-                        target,
-                        range: PIXELS_PER_METER * 5.0,
-                    }),
+                    AndTrigger(
+                        NotTrigger(Near {
+                            target,
+                            range: PIXELS_PER_METER * 5.0,
+                        }),
+                        LineOfSight {
+                            target,
+                            range: PIXELS_PER_METER * 20.0,
+                        },
+                    ),
                     FireProjectileAndKeepDistance {
                         fire_projectile: FireProjectile {
                             target,
@@ -430,8 +538,20 @@ fn spawn_mutant(
                         inner_distance: PIXELS_PER_METER * 2.0,
                         outer_distance: PIXELS_PER_METER * 6.0,
                     },
+                )
+                .trans::<FireProjectileAndKeepDistance>(
+                    NotTrigger(LineOfSight {
+                        target,
+                        range: PIXELS_PER_METER * 20.0,
+                    }),
+                    ApproachAndKeepDistance {
+                        target,
+                        inner_distance: PIXELS_PER_METER * 4.0,
+                        outer_distance: PIXELS_PER_METER * 6.0,
+                    },
                 ),
-        );
+        )
+        .id()
 }
 
 fn spawn_goblin(
@@ -439,8 +559,8 @@ fn spawn_goblin(
     sprites: &SpriteAssets,
     texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
     transform: Transform,
-    target: Entity,
-) {
+    target: TargetRole,
+) -> Entity {
     // Spawn the entity
     let texture_atlas_handle = texture_atlases.add(TextureAtlas::from_grid(
         sprites.goblin.clone(),
@@ -456,7 +576,7 @@ fn spawn_goblin(
     let mut timer = Timer::from_seconds(0.40, TimerMode::Repeating);
     timer.tick(Duration::from_millis(rng.gen_range(0..=150)));
 
-    let _enemy_entity = commands
+    commands
         .spawn(GoblinBundle::new(
             texture_atlas_handle,
             sprite_size,
@@ -510,7 +630,8 @@ fn spawn_goblin(
                     }),
                     Idle,
                 ),
-        );
+        )
+        .id()
 }
 
 fn spawn_goblin_brute(
@@ -518,8 +639,8 @@ fn spawn_goblin_brute(
     sprites: &SpriteAssets,
     texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
     transform: Transform,
-    target: Entity,
-) {
+    target: TargetRole,
+) -> Entity {
     // Spawn the entity
     let texture_atlas_handle = texture_atlases.add(TextureAtlas::from_grid(
         sprites.goblin.clone(),
@@ -535,7 +656,7 @@ fn spawn_goblin_brute(
     let mut timer = Timer::from_seconds(0.40, TimerMode::Repeating);
     timer.tick(Duration::from_millis(rng.gen_range(0..=150)));
 
-    let _enemy_entity = commands
+    commands
         .spawn(GoblinBundle::new(
             texture_atlas_handle,
             sprite_size,
@@ -549,6 +670,7 @@ fn spawn_goblin_brute(
         ))
         .insert(ActiveCollisionTypes::STATIC_STATIC)
         .insert(FacePlayer)
+        .insert(DashCooldown::new(2.0))
         .insert(
             // This state machine handles the enemy's transitions
             // The initial state is `Idle`
@@ -588,8 +710,25 @@ fn spawn_goblin_brute(
                         range: PIXELS_PER_METER * 20.0,
                     }),
                     Idle,
+                )
+                // Charge at the player when they're at mid range
+                .trans::<ApproachAndKeepDistance>(
+                    CanDash {
+                        target,
+                        range: PIXELS_PER_METER * 10.0,
+                    },
+                    Dash::new(target, PIXELS_PER_METER * 20.0),
+                )
+                .trans::<Dash>(
+                    DashFinished,
+                    ApproachAndKeepDistance {
+                        target,
+                        inner_distance: PIXELS_PER_METER * 4.0,
+                        outer_distance: PIXELS_PER_METER * 6.0,
+                    },
                 ),
-        );
+        )
+        .id()
 }
 
 fn spawn_adept(
@@ -597,8 +736,8 @@ fn spawn_adept(
     sprites: &SpriteAssets,
     texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
     transform: Transform,
-    target: Entity,
-) {
+    target: TargetRole,
+) -> Entity {
     // Spawn the entity
     let texture_atlas_handle = texture_atlases.add(TextureAtlas::from_grid(
         sprites.adept.clone(),
@@ -616,7 +755,7 @@ fn spawn_adept(
 
     let _rng = rand::thread_rng();
 
-    let _enemy_entity = commands
+    commands
         .spawn(AdeptBundle::new(
             texture_atlas_handle,
             sprite_size,
@@ -670,7 +809,8 @@ fn spawn_adept(
                     }),
                     Idle,
                 ),
-        );
+        )
+        .id()
 }
 
 fn spawn_lab_boss(
@@ -678,8 +818,8 @@ fn spawn_lab_boss(
     sprites: &SpriteAssets,
     texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
     transform: Transform,
-    target: Entity,
-) {
+    target: TargetRole,
+) -> Entity {
     // Spawn the entity
     let texture_atlas_handle = texture_atlases.add(TextureAtlas::from_grid(
         sprites.lab_boss.clone(),
@@ -695,61 +835,25 @@ fn spawn_lab_boss(
     let mut timer = Timer::from_seconds(0.9, TimerMode::Repeating);
     timer.tick(Duration::from_millis(rng.gen_range(0..=150)));
 
-    let _enemy_entity = commands
-        .spawn(LabBossBundle::new(
-            texture_atlas_handle,
-            sprite_size,
-            Animated {
-                timer,
-                first: 0,
-                last: 1,
-                ..default()
-            },
-            transform,
-        ))
+    let phases = lab_boss_phases(target);
+    let mut lab_boss = commands.spawn(LabBossBundle::new(
+        texture_atlas_handle,
+        sprite_size,
+        Animated {
+            timer,
+            first: 0,
+            last: 1,
+            ..default()
+        },
+        transform,
+    ));
+    lab_boss
         .insert(ActiveCollisionTypes::STATIC_STATIC)
         .insert(FacePlayer)
-        .insert(
-            // This state machine handles the enemy's transitions
-            // The initial state is `Idle`
-            StateMachine::new(Idle)
-                .trans::<Idle>(
-                    Near {
-                        target,
-                        range: PIXELS_PER_METER * 150.0,
-                    },
-                    ApproachAndKeepDistance {
-                        target,
-                        inner_distance: PIXELS_PER_METER * 3.5,
-                        outer_distance: PIXELS_PER_METER * 5.0,
-                    },
-                )
-                .trans::<ApproachAndKeepDistance>(
-                    Near {
-                        target,
-                        range: PIXELS_PER_METER * 4.0,
-                    },
-                    AttackAndKeepDistance {
-                        approach_and_keep_distance: ApproachAndKeepDistance {
-                            target,
-                            inner_distance: PIXELS_PER_METER * 1.0,
-                            outer_distance: PIXELS_PER_METER * 1.5,
-                        },
-                        attack: LabBossAttack,
-                    },
-                )
-                .trans::<AttackAndKeepDistance>(
-                    NotTrigger(Near {
-                        target,
-                        range: PIXELS_PER_METER * 3.0,
-                    }),
-                    ApproachAndKeepDistance {
-                        target,
-                        inner_distance: PIXELS_PER_METER * 3.5,
-                        outer_distance: PIXELS_PER_METER * 5.0,
-                    },
-                ),
-        );
+        .insert(DashCooldown::new(3.0));
+    // The state machine and attacks come from the current phase
+    phases.insert_phase(&mut lab_boss, 0);
+    lab_boss.insert(phases).id()
 }
 
 fn spawn_sorcerian(
@@ -757,8 +861,8 @@ fn spawn_sorcerian(
     sprites: &SpriteAssets,
     texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
     transform: Transform,
-    target: Entity,
-) {
+    target: TargetRole,
+) -> Entity {
     // Spawn the entity
     let texture_atlas_handle = texture_atlases.add(TextureAtlas::from_grid(
         sprites.sorcerian.clone(),
@@ -774,64 +878,22 @@ fn spawn_sorcerian(
     let mut timer = Timer::from_seconds(0.40, TimerMode::Repeating);
     timer.tick(Duration::from_millis(rng.gen_range(0..=150)));
 
-    let _enemy_entity = commands
-        .spawn(SorcerianBundle::new(
-            texture_atlas_handle,
-            sprite_size,
-            Animated {
-                timer,
-                first: 0,
-                last: 1,
-                ..default()
-            },
-            transform,
-        ))
+    let phases = sorcerian_phases(target);
+    let mut sorcerian = commands.spawn(SorcerianBundle::new(
+        texture_atlas_handle,
+        sprite_size,
+        Animated {
+            timer,
+            first: 0,
+            last: 1,
+            ..default()
+        },
+        transform,
+    ));
+    sorcerian
         .insert(ActiveCollisionTypes::STATIC_STATIC)
-        .insert(FacePlayer)
-        .insert(
-            // This state machine handles the enemy's transitions
-            // The initial state is `Idle`
-            StateMachine::new(Idle)
-                .trans::<Idle>(
-                    Near {
-                        target,
-                        range: PIXELS_PER_METER * 20.0,
-                    },
-                    ApproachAndKeepDistance {
-                        target,
-                        inner_distance: PIXELS_PER_METER * 5.0,
-                        outer_distance: PIXELS_PER_METER * 8.0,
-                    },
-                )
-                .trans::<Idle>(
-                    NotTrigger(Near {
-                        target,
-                        range: PIXELS_PER_METER * 20.0,
-                    }),
-                    Wander::default(),
-                )
-                .trans::<Wander>(
-                    Near {
-                        target,
-                        range: PIXELS_PER_METER * 20.0,
-                    },
-                    ApproachAndKeepDistance {
-                        target,
-                        inner_distance: PIXELS_PER_METER * 5.0,
-                        outer_distance: PIXELS_PER_METER * 8.0,
-                    },
-                )
-                .trans::<ApproachAndKeepDistance>(
-                    NotTrigger(Near {
-                        target,
-                        range: PIXELS_PER_METER * 20.0,
-                    }),
-                    Idle,
-                ),
-        );
-}
-
-#[derive(Reflect, Clone, Copy)]
-pub enum Projectile {
-    MutantProjectile,
+        .insert(FacePlayer);
+    // The state machine and attacks come from the current phase
+    phases.insert_phase(&mut sorcerian, 0);
+    sorcerian.insert(phases).id()
 }
@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, Sensor};
+
+use crate::{game::GameState, PIXELS_PER_METER};
+
+use super::{
+    creature::Lifetime,
+    faction::Faction,
+    projectile::{spawn_projectiles, Projectile, ProjectileAtlases},
+    EnemyHurtbox, EnemyHurtboxDamage,
+};
+
+// How opaque the indicator gets right before the attack goes off
+const TELEGRAPH_MAX_ALPHA: f32 = 0.5;
+
+// Points used for the curved edge of cones
+const CONE_SEGMENTS: usize = 8;
+
+pub struct TelegraphPlugin;
+
+impl Plugin for TelegraphPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ShapePlugin)
+            .add_system(telegraph_system.in_set(OnUpdate(GameState::InLevel)));
+    }
+}
+
+// The area an attack is going to hit, in the telegraph's local space. Rectangles and circles are
+// centered on it and cones point along the x axis
+#[derive(Clone, Copy, Debug)]
+pub enum TelegraphShape {
+    Circle { radius: f32 },
+    Rectangle { half_extents: Vec2 },
+    Cone { range: f32, angle: f32 },
+}
+
+impl TelegraphShape {
+    fn cone_points(range: f32, angle: f32) -> Vec<Vec2> {
+        let mut points = vec![Vec2::ZERO];
+        points.extend((0..=CONE_SEGMENTS).map(|i| {
+            let t = i as f32 / CONE_SEGMENTS as f32;
+            Vec2::from_angle(-angle / 2.0 + angle * t) * range
+        }));
+        points
+    }
+
+    fn path(&self) -> Path {
+        match *self {
+            TelegraphShape::Circle { radius } => GeometryBuilder::build_as(&shapes::Circle {
+                radius,
+                center: Vec2::ZERO,
+            }),
+            TelegraphShape::Rectangle { half_extents } => {
+                GeometryBuilder::build_as(&shapes::Rectangle {
+                    extents: half_extents * 2.0,
+                    origin: RectangleOrigin::Center,
+                })
+            }
+            TelegraphShape::Cone { range, angle } => GeometryBuilder::build_as(&shapes::Polygon {
+                points: Self::cone_points(range, angle),
+                closed: true,
+            }),
+        }
+    }
+
+    fn collider(&self) -> Collider {
+        match *self {
+            TelegraphShape::Circle { radius } => Collider::ball(radius),
+            TelegraphShape::Rectangle { half_extents } => {
+                Collider::cuboid(half_extents.x, half_extents.y)
+            }
+            TelegraphShape::Cone { range, angle } => {
+                Collider::convex_hull(&Self::cone_points(range, angle))
+                    .unwrap_or_else(|| Collider::ball(range))
+            }
+        }
+    }
+}
+
+// What goes off once the wind up is over
+#[derive(Clone, Copy, Debug)]
+pub enum TelegraphedAttack {
+    // A hurtbox the same shape as the indicator
+    Hurtbox { damage: u32, lifetime: f32 },
+    // A shot fired from the attacker along the telegraph's direction
+    Projectile { projectile: Projectile },
+}
+
+// Ground indicator for an enemy attack that's winding up. Lives as a child of the attacker, so it
+// goes away with them if they die first
+#[derive(Component)]
+pub struct Telegraph {
+    pub wind_up: Timer,
+    pub shape: TelegraphShape,
+    pub attack: TelegraphedAttack,
+    pub direction: Vec2,
+}
+
+pub fn spawn_telegraph(
+    commands: &mut Commands,
+    attacker: Entity,
+    offset: Vec2,
+    direction: Vec2,
+    wind_up: f32,
+    shape: TelegraphShape,
+    attack: TelegraphedAttack,
+) {
+    let rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+    commands.entity(attacker).with_children(|parent| {
+        parent.spawn((
+            ShapeBundle {
+                path: shape.path(),
+                // Just below the attacker
+                transform: Transform::from_translation(offset.extend(-0.5)).with_rotation(rotation),
+                ..default()
+            },
+            Fill::color(Color::rgba(1.0, 0.15, 0.1, 0.0)),
+            Telegraph {
+                wind_up: Timer::from_seconds(wind_up, TimerMode::Once),
+                shape,
+                attack,
+                direction,
+            },
+        ));
+    });
+}
+
+// Shows the lane, or the cone for spreads, that the shot is going to take
+pub fn telegraph_projectile(
+    commands: &mut Commands,
+    attacker: Entity,
+    projectile: Projectile,
+    direction: Vec2,
+) {
+    let definition = projectile.definition();
+    let direction = direction.normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return;
+    }
+    let length = (definition.speed * 0.75).max(PIXELS_PER_METER * 2.0);
+
+    let (offset, shape) = if definition.count > 1 {
+        (
+            Vec2::ZERO,
+            TelegraphShape::Cone {
+                range: length,
+                angle: definition.spread,
+            },
+        )
+    } else {
+        (
+            direction * length / 2.0,
+            TelegraphShape::Rectangle {
+                half_extents: Vec2::new(length / 2.0, definition.radius),
+            },
+        )
+    };
+
+    spawn_telegraph(
+        commands,
+        attacker,
+        offset,
+        direction,
+        definition.wind_up,
+        shape,
+        TelegraphedAttack::Projectile { projectile },
+    );
+}
+
+pub fn telegraph_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    projectile_atlases: Res<ProjectileAtlases>,
+    mut telegraph_query: Query<(Entity, &Parent, &Transform, &mut Telegraph, &mut Fill)>,
+    attacker_query: Query<(&GlobalTransform, Option<&Faction>)>,
+) {
+    for (entity, parent, transform, mut telegraph, mut fill) in telegraph_query.iter_mut() {
+        telegraph.wind_up.tick(time.delta());
+
+        // Fades in over the wind up and flickers right before going off
+        let progress = telegraph.wind_up.percent();
+        let flicker = if progress > 0.8 && (progress * 40.0) as u32 % 2 == 0 {
+            0.5
+        } else {
+            1.0
+        };
+        fill.color.set_a(TELEGRAPH_MAX_ALPHA * progress * flicker);
+
+        if !telegraph.wind_up.finished() {
+            continue;
+        }
+
+        match telegraph.attack {
+            TelegraphedAttack::Hurtbox { damage, lifetime } => {
+                let collider = telegraph.shape.collider();
+                commands.entity(parent.get()).with_children(|parent| {
+                    parent.spawn((
+                        EnemyHurtbox {
+                            collider,
+                            damage: EnemyHurtboxDamage(damage),
+                            sensor: Sensor,
+                            transform: *transform,
+                            ..default()
+                        },
+                        ActiveCollisionTypes::STATIC_STATIC,
+                        Lifetime {
+                            timer: Timer::from_seconds(lifetime, TimerMode::Once),
+                        },
+                    ));
+                });
+            }
+            TelegraphedAttack::Projectile { projectile } => {
+                // Charmed attackers shoot for the player's side
+                if let Ok((attacker_transform, faction)) = attacker_query.get(parent.get()) {
+                    spawn_projectiles(
+                        &mut commands,
+                        &projectile_atlases,
+                        projectile,
+                        faction.copied().unwrap_or_default(),
+                        attacker_transform.translation(),
+                        telegraph.direction,
+                    );
+                }
+            }
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+}
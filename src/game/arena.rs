@@ -0,0 +1,327 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::LdtkLevel;
+use bevy_kira_audio::{AudioChannel, AudioControl};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    app_state::{
+        loading::{Background, MusicAssets},
+        AppState,
+    },
+    entity::{
+        creature::EnemyKilled,
+        player::Player,
+        spawner::{EnemyType, Spawner},
+    },
+};
+
+use super::{
+    level_manager::{LevelObject, SpawnerBundle},
+    mutation_manager::MutationManager,
+    GameMode, GameState,
+};
+
+// The LDtk level used for arena runs. Falls back to the first level if the project doesn't have one.
+pub const ARENA_LEVEL: &str = "Arena";
+
+// Spawners are never placed closer than this to the player
+const MIN_SPAWN_DISTANCE: f32 = 256.0;
+
+// Elite chance of arena spawners, grows with every wave up to the cap
+const ARENA_ELITE_CHANCE_PER_WAVE: f32 = 0.03;
+const ARENA_MAX_ELITE_CHANCE: f32 = 0.4;
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(arena_setup.in_schedule(OnEnter(GameState::SetupLevelManager)))
+            .add_system(arena_cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(arena_spawn_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(arena_kill_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(arena_hud_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(arena_pause.in_schedule(OnEnter(GameState::MutationSelection)))
+            .add_system(arena_resume.in_schedule(OnExit(GameState::MutationSelection)));
+    }
+}
+
+#[derive(Resource)]
+pub struct Arena {
+    pub score: u32,
+    pub kills: u32,
+    pub survival_time: f32,
+    pub wave: u32,
+    pub kills_per_mutation: u32,
+    pub kills_since_mutation: u32,
+    pub spawn_timer: Timer,
+    pub hud: Entity,
+}
+
+impl Arena {
+    // Later waves bring more elites
+    fn elite_chance(&self) -> f32 {
+        (ARENA_ELITE_CHANCE_PER_WAVE * self.wave as f32).min(ARENA_MAX_ELITE_CHANCE)
+    }
+
+    // Enemy types join the mix the longer the player survives
+    fn enemy_pool(&self) -> Vec<EnemyType> {
+        let mut pool = vec![EnemyType::Slimer, EnemyType::Skuller];
+        if self.survival_time > 30.0 {
+            pool.push(EnemyType::Goblin);
+        }
+        if self.survival_time > 60.0 {
+            pool.push(EnemyType::Mutant);
+        }
+        if self.survival_time > 90.0 {
+            pool.push(EnemyType::Adept);
+        }
+        if self.survival_time > 120.0 {
+            pool.push(EnemyType::GoblinBrute);
+        }
+        pool
+    }
+
+    // Seconds between waves, shrinking over time
+    fn spawn_interval(&self) -> f32 {
+        (8.0 - self.survival_time / 30.0).max(2.0)
+    }
+
+    // Survival time counts towards the score as one point per second
+    pub fn final_score(&self) -> u32 {
+        self.score + self.survival_time as u32
+    }
+}
+
+#[derive(Component)]
+pub struct ArenaHudText;
+
+pub fn arena_setup(
+    mut commands: Commands,
+    game_mode: Res<GameMode>,
+    asset_server: Res<AssetServer>,
+    arena: Option<Res<Arena>>,
+) {
+    if *game_mode != GameMode::Arena {
+        return;
+    }
+    // Retrying replaces the previous attempt
+    if let Some(arena) = arena {
+        commands.entity(arena.hud).despawn_recursive();
+    }
+
+    let hud = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(10.0),
+                    right: Val::Px(20.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                ArenaHudText,
+            ));
+        })
+        .id();
+
+    commands.insert_resource(Arena {
+        score: 0,
+        kills: 0,
+        survival_time: 0.0,
+        wave: 0,
+        kills_per_mutation: 25,
+        kills_since_mutation: 0,
+        spawn_timer: Timer::from_seconds(2.0, TimerMode::Once),
+        hud,
+    });
+}
+
+pub fn arena_cleanup(mut commands: Commands, arena: Option<Res<Arena>>, mut time: ResMut<Time>) {
+    if let Some(arena) = arena {
+        commands.entity(arena.hud).despawn_recursive();
+        commands.remove_resource::<Arena>();
+    }
+    time.unpause();
+}
+
+// Keep opening portals around the arena, with more enemies and tougher types as time goes on
+pub fn arena_spawn_system(
+    mut commands: Commands,
+    arena: Option<ResMut<Arena>>,
+    time: Res<Time>,
+    level_query: Query<(&Transform, &Handle<LdtkLevel>)>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let mut arena = match arena {
+        Some(arena) => arena,
+        None => return,
+    };
+    arena.survival_time += time.delta_seconds();
+
+    arena.spawn_timer.tick(time.delta());
+    if !arena.spawn_timer.finished() {
+        return;
+    }
+    let interval = arena.spawn_interval();
+    arena.spawn_timer = Timer::from_seconds(interval, TimerMode::Once);
+    arena.wave += 1;
+
+    let (level_transform, level_handle) = match level_query.get_single() {
+        Ok(level) => level,
+        Err(_) => return,
+    };
+    let ldtk_level = match ldtk_levels.get(level_handle) {
+        Some(ldtk_level) => ldtk_level,
+        None => return,
+    };
+    let player_position = player_query
+        .get_single()
+        .map(|transform| transform.translation.truncate())
+        .unwrap_or_default();
+
+    let floor_positions: Vec<Vec2> = floor_cell_positions(ldtk_level)
+        .into_iter()
+        .map(|position| level_transform.translation.truncate() + position)
+        .filter(|position| position.distance(player_position) > MIN_SPAWN_DISTANCE)
+        .collect();
+
+    let mut rng = rand::thread_rng();
+    let pool = arena.enemy_pool();
+    let portals = 1 + arena.wave / 4;
+    for _ in 0..portals {
+        let (position, enemy_type) = match (floor_positions.choose(&mut rng), pool.choose(&mut rng))
+        {
+            (Some(position), Some(enemy_type)) => (*position, enemy_type.clone()),
+            _ => return,
+        };
+        let spawn_count = rng.gen_range(2..=3 + arena.wave as usize / 2);
+
+        commands.spawn((
+            SpawnerBundle {
+                spawner: Spawner {
+                    timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+                    spawn_rate: 1,
+                    spawn_count,
+                    enemy_type,
+                    elite_chance: arena.elite_chance(),
+                },
+            },
+            Transform::from_translation(position.extend(0.0)),
+            GlobalTransform::default(),
+            LevelObject,
+        ));
+    }
+}
+
+// Centers of every IntGrid cell that isn't a wall, relative to the level's origin
+fn floor_cell_positions(ldtk_level: &LdtkLevel) -> Vec<Vec2> {
+    let level = &ldtk_level.level;
+    let int_grid = level
+        .layer_instances
+        .iter()
+        .flatten()
+        .find(|layer| !layer.int_grid_csv.is_empty());
+
+    match int_grid {
+        Some(layer) => {
+            let grid_size = layer.grid_size as f32;
+            layer
+                .int_grid_csv
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| **value == 0)
+                .map(|(i, _)| {
+                    let x = (i as i32 % layer.c_wid) as f32;
+                    let y = (i as i32 / layer.c_wid) as f32;
+                    // LDtk counts rows from the top
+                    Vec2::new(
+                        x * grid_size + grid_size / 2.0,
+                        level.px_hei as f32 - (y * grid_size + grid_size / 2.0),
+                    )
+                })
+                .collect()
+        }
+        None => vec![Vec2::new(level.px_wid as f32, level.px_hei as f32) / 2.0],
+    }
+}
+
+// Every few kills the player gets to pick a mutation
+pub fn arena_kill_system(
+    arena: Option<ResMut<Arena>>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mutation_manager: Res<MutationManager>,
+) {
+    let mut arena = match arena {
+        Some(arena) => arena,
+        None => return,
+    };
+
+    for _ in enemy_killed.iter() {
+        arena.kills += 1;
+        arena.kills_since_mutation += 1;
+        arena.score += 10 * (1 + arena.wave / 5);
+    }
+
+    if arena.kills_since_mutation >= arena.kills_per_mutation
+        && !mutation_manager.unselected_mutations().is_empty()
+    {
+        arena.kills_since_mutation = 0;
+        next_state.set(GameState::MutationSelection);
+    }
+}
+
+pub fn arena_hud_system(
+    arena: Option<Res<Arena>>,
+    mut text_query: Query<&mut Text, With<ArenaHudText>>,
+) {
+    let arena = match arena {
+        Some(arena) => arena,
+        None => return,
+    };
+
+    let seconds = arena.survival_time as u32;
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "Score {}  Kills {}  {}:{:02}",
+            arena.final_score(),
+            arena.kills,
+            seconds / 60,
+            seconds % 60
+        );
+    }
+}
+
+// The arena stays loaded while picking a mutation, so freeze it
+pub fn arena_pause(arena: Option<Res<Arena>>, mut time: ResMut<Time>) {
+    if arena.is_some() {
+        time.pause();
+    }
+}
+
+pub fn arena_resume(
+    arena: Option<Res<Arena>>,
+    mut time: ResMut<Time>,
+    background: Res<AudioChannel<Background>>,
+    music_assets: Res<MusicAssets>,
+) {
+    if arena.is_some() {
+        time.unpause();
+        background.stop();
+        background.play(music_assets.crystal.clone()).looped();
+    }
+}
@@ -0,0 +1,432 @@
+use std::str::FromStr;
+
+use bevy::{prelude::*, render::texture::DEFAULT_IMAGE_HANDLE};
+use bevy_ecs_ldtk::{
+    prelude::{FieldValue, LdtkEntityAppExt},
+    EntityInstance, LdtkEntity,
+};
+use bevy_rapier2d::prelude::Collider;
+
+use crate::{
+    behaviour::boss_phase::BossPhases,
+    camera::CameraFocus,
+    entity::{
+        creature::Creature,
+        player::Player,
+        spawner::{EnemyKind, EnemyType, Spawner},
+        Enemy,
+    },
+    PIXELS_PER_METER,
+};
+
+use super::{
+    level_manager::{entity_half_extents, LevelObject, Wall},
+    GameState,
+};
+
+// How far creatures are kept from a seal's edge
+const SEAL_MARGIN: f32 = PIXELS_PER_METER * 0.5;
+
+pub struct BossEncounterPlugin;
+
+impl Plugin for BossEncounterPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.register_ldtk_entity::<BossEncounterEntityBundle>("BossEncounter")
+            .register_ldtk_entity::<BossSealEntityBundle>("BossSeal");
+
+        app.add_system(boss_encounter_trigger.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(boss_encounter_intro.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(boss_encounter_fight.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(boss_seal_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(boss_encounter_cleanup.in_schedule(OnExit(GameState::InLevel)));
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub enum EncounterStage {
+    #[default]
+    Waiting,
+    Intro {
+        boss: Entity,
+        timer: Timer,
+    },
+    Fighting,
+    Defeated,
+}
+
+impl EncounterStage {
+    // Exits stay shut until the boss is dead
+    pub fn blocks_exits(&self) -> bool {
+        !matches!(self, EncounterStage::Defeated)
+    }
+}
+
+// Starts the boss fight once the player walks into the entity's area: the camera pans to the
+// boss while the game is paused and the boss' name is shown, then the seals close until it dies
+#[derive(Component, Clone, Default, Debug)]
+pub struct BossEncounter {
+    pub boss_name: String,
+    pub boss_title: Option<String>,
+    // Which enemy is the boss, any enemy with boss phases if not set
+    pub boss_type: Option<EnemyType>,
+    pub intro_seconds: f32,
+    pub seal_exits: bool,
+    pub half_extents: Vec2,
+    pub stage: EncounterStage,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct BossEncounterEntityBundle {
+    #[from_entity_instance]
+    #[bundle]
+    pub encounter: BossEncounterBundle,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct BossEncounterBundle {
+    pub encounter: BossEncounter,
+    pub level_object: LevelObject,
+}
+
+// Blocks off part of the level while a boss fight is going on
+#[derive(Component, Clone, Default, Debug)]
+pub struct BossSeal {
+    pub half_extents: Vec2,
+    pub active: bool,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct BossSealEntityBundle {
+    #[from_entity_instance]
+    #[bundle]
+    pub seal: BossSealBundle,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct BossSealBundle {
+    pub seal: BossSeal,
+    pub level_object: LevelObject,
+}
+
+#[derive(Component)]
+pub struct BossNameCard;
+
+fn find_field_value<'a>(
+    entity_instance: &'a EntityInstance,
+    field_identifier: &str,
+) -> Option<&'a FieldValue> {
+    entity_instance
+        .field_instances
+        .iter()
+        .find(|f| f.identifier.as_str() == field_identifier)
+        .map(|field_instance| &field_instance.value)
+}
+
+impl From<&EntityInstance> for BossEncounterBundle {
+    fn from(entity_instance: &EntityInstance) -> BossEncounterBundle {
+        // All fields are optional, the defaults work for a single boss
+        let boss_type = match find_field_value(entity_instance, "boss_type") {
+            Some(FieldValue::Enum(Some(boss_type))) => EnemyType::from_str(boss_type).ok(),
+            _ => None,
+        };
+        let boss_name = match find_field_value(entity_instance, "boss_name") {
+            Some(FieldValue::String(Some(boss_name))) if !boss_name.is_empty() => boss_name.clone(),
+            _ => boss_type
+                .as_ref()
+                .map(|boss_type| boss_type.identifier().to_string())
+                .unwrap_or_else(|| "Boss".to_string()),
+        };
+        let boss_title = match find_field_value(entity_instance, "boss_title") {
+            Some(FieldValue::String(Some(boss_title))) if !boss_title.is_empty() => {
+                Some(boss_title.clone())
+            }
+            _ => None,
+        };
+        let intro_seconds = match find_field_value(entity_instance, "intro_seconds") {
+            Some(FieldValue::Float(Some(intro_seconds))) => intro_seconds.max(0.0),
+            _ => 2.5,
+        };
+        let seal_exits = match find_field_value(entity_instance, "seal_exits") {
+            Some(FieldValue::Bool(seal_exits)) => *seal_exits,
+            _ => true,
+        };
+
+        BossEncounterBundle {
+            encounter: BossEncounter {
+                boss_name,
+                boss_title,
+                boss_type,
+                intro_seconds,
+                seal_exits,
+                half_extents: entity_half_extents(entity_instance),
+                stage: EncounterStage::Waiting,
+            },
+            level_object: LevelObject,
+        }
+    }
+}
+
+impl From<&EntityInstance> for BossSealBundle {
+    fn from(entity_instance: &EntityInstance) -> BossSealBundle {
+        BossSealBundle {
+            seal: BossSeal {
+                half_extents: entity_half_extents(entity_instance),
+                active: false,
+            },
+            level_object: LevelObject,
+        }
+    }
+}
+
+// Whether an enemy of the given type is one of the encounter's bosses
+fn is_encounter_boss(boss_type: &Option<EnemyType>, enemy_type: Option<&EnemyType>) -> bool {
+    match (boss_type, enemy_type) {
+        (Some(boss_type), Some(enemy_type)) => boss_type == enemy_type,
+        (Some(_), None) => false,
+        (None, _) => true,
+    }
+}
+
+fn contains(center: Vec2, half_extents: Vec2, point: Vec2) -> bool {
+    let offset = (point - center).abs();
+    offset.x <= half_extents.x && offset.y <= half_extents.y
+}
+
+fn spawn_name_card(commands: &mut Commands, asset_server: &AssetServer, encounter: &BossEncounter) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let mut lines = vec![(encounter.boss_name.clone(), 80.0)];
+    if let Some(boss_title) = &encounter.boss_title {
+        lines.push((boss_title.clone(), 30.0));
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(30.0)),
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Percent(10.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            BossNameCard,
+            LevelObject,
+        ))
+        .with_children(|parent| {
+            for (line, font_size) in lines {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size,
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+        });
+}
+
+pub fn boss_encounter_trigger(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    asset_server: Res<AssetServer>,
+    mut encounter_query: Query<(&GlobalTransform, &mut BossEncounter)>,
+    mut seal_query: Query<&mut BossSeal>,
+    player_query: Query<&Transform, With<Player>>,
+    mut boss_query: Query<(Entity, &Transform, &mut BossPhases, Option<&EnemyKind>)>,
+) {
+    let player_position = match player_query.get_single() {
+        Ok(player_transform) => player_transform.translation.truncate(),
+        Err(_) => return,
+    };
+
+    for (encounter_transform, mut encounter) in encounter_query.iter_mut() {
+        if !matches!(encounter.stage, EncounterStage::Waiting) {
+            continue;
+        }
+        let center = encounter_transform.translation().truncate();
+        if !contains(center, encounter.half_extents, player_position) {
+            continue;
+        }
+
+        // The boss might not have come out of its spawner yet, keep checking until it has
+        let boss = boss_query
+            .iter_mut()
+            .filter(|(_, _, _, kind)| {
+                is_encounter_boss(&encounter.boss_type, kind.map(|kind| &kind.0))
+            })
+            .min_by(|(_, a, _, _), (_, b, _, _)| {
+                let a = a.translation.truncate().distance_squared(center);
+                let b = b.translation.truncate().distance_squared(center);
+                a.total_cmp(&b)
+            });
+        let (boss, _, mut phases, _) = match boss {
+            Some(boss) => boss,
+            None => continue,
+        };
+
+        // The enrage timer shouldn't count the time before the fight
+        phases.enrage_timer.reset();
+
+        if encounter.seal_exits {
+            for mut seal in seal_query.iter_mut() {
+                seal.active = true;
+            }
+        }
+
+        spawn_name_card(&mut commands, &asset_server, &encounter);
+        time.set_relative_speed(0.0);
+        encounter.stage = EncounterStage::Intro {
+            boss,
+            timer: Timer::from_seconds(encounter.intro_seconds, TimerMode::Once),
+        };
+    }
+}
+
+// Keep the camera on the boss until the intro is over, then hand control back to the player
+pub fn boss_encounter_intro(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    mut camera_focus: ResMut<CameraFocus>,
+    mut encounter_query: Query<&mut BossEncounter>,
+    transforms: Query<&Transform>,
+    name_card_query: Query<Entity, With<BossNameCard>>,
+) {
+    for mut encounter in encounter_query.iter_mut() {
+        let (boss, timer) = match &mut encounter.stage {
+            EncounterStage::Intro { boss, timer } => (*boss, timer),
+            _ => continue,
+        };
+
+        if let Ok(boss_transform) = transforms.get(boss) {
+            camera_focus.target = Some(boss_transform.translation.truncate());
+        }
+
+        // Real time, the game itself is paused
+        if timer.tick(time.raw_delta()).finished() {
+            time.set_relative_speed(1.0);
+            camera_focus.target = None;
+            for name_card in name_card_query.iter() {
+                commands.entity(name_card).despawn_recursive();
+            }
+            encounter.stage = EncounterStage::Fighting;
+        }
+    }
+}
+
+// Once every boss is dead their minions go with them and the seals open, so the level can be
+// completed
+pub fn boss_encounter_fight(
+    mut commands: Commands,
+    mut encounter_query: Query<&mut BossEncounter>,
+    mut seal_query: Query<&mut BossSeal>,
+    boss_query: Query<Option<&EnemyKind>, With<BossPhases>>,
+    spawner_query: Query<&Spawner>,
+    leftover_query: Query<Entity, Or<(With<Enemy>, With<Spawner>)>>,
+) {
+    for mut encounter in encounter_query.iter_mut() {
+        if !matches!(encounter.stage, EncounterStage::Fighting) {
+            continue;
+        }
+
+        // Bosses still waiting in their spawner count too
+        let bosses_left = boss_query
+            .iter()
+            .any(|kind| is_encounter_boss(&encounter.boss_type, kind.map(|kind| &kind.0)))
+            || spawner_query.iter().any(|spawner| {
+                spawner.spawn_count > 0
+                    && spawner.enemy_type.is_boss()
+                    && is_encounter_boss(&encounter.boss_type, Some(&spawner.enemy_type))
+            });
+        if bosses_left {
+            continue;
+        }
+
+        for entity in leftover_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        for mut seal in seal_query.iter_mut() {
+            seal.active = false;
+        }
+        encounter.stage = EncounterStage::Defeated;
+    }
+}
+
+// Walls don't physically stop anything, so active seals push creatures back out themselves
+pub fn boss_seal_system(
+    mut commands: Commands,
+    seal_query: Query<(Entity, &GlobalTransform, &BossSeal, Option<&Wall>)>,
+    mut creature_query: Query<&mut Transform, With<Creature>>,
+) {
+    for (entity, seal_transform, seal, wall) in seal_query.iter() {
+        // Give the seal a collider and a sprite while it's closed
+        match (seal.active, wall.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert((
+                    Collider::cuboid(seal.half_extents.x, seal.half_extents.y),
+                    Wall,
+                    Sprite {
+                        color: Color::rgba(0.8, 0.1, 0.1, 0.6),
+                        custom_size: Some(seal.half_extents * 2.0),
+                        ..default()
+                    },
+                    DEFAULT_IMAGE_HANDLE.typed::<Image>(),
+                    Visibility::default(),
+                    ComputedVisibility::default(),
+                ));
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<(
+                    Collider,
+                    Wall,
+                    Sprite,
+                    Handle<Image>,
+                    Visibility,
+                    ComputedVisibility,
+                )>();
+            }
+            _ => {}
+        }
+        if !seal.active {
+            continue;
+        }
+
+        let center = seal_transform.translation().truncate();
+        let half_extents = seal.half_extents + Vec2::splat(SEAL_MARGIN);
+        for mut transform in creature_query.iter_mut() {
+            let offset = transform.translation.truncate() - center;
+            let overlap = half_extents - offset.abs();
+            if overlap.x <= 0.0 || overlap.y <= 0.0 {
+                continue;
+            }
+
+            // Out along whichever side is closest
+            if overlap.x < overlap.y {
+                transform.translation.x += overlap.x * offset.x.signum();
+            } else {
+                transform.translation.y += overlap.y * offset.y.signum();
+            }
+        }
+    }
+}
+
+// Leaving the level mid intro, e.g. by dying, shouldn't leave the game paused or the camera stuck
+pub fn boss_encounter_cleanup(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    mut camera_focus: ResMut<CameraFocus>,
+    name_card_query: Query<Entity, With<BossNameCard>>,
+) {
+    time.set_relative_speed(1.0);
+    camera_focus.target = None;
+    for name_card in name_card_query.iter() {
+        commands.entity(name_card).despawn_recursive();
+    }
+}
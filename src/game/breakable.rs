@@ -0,0 +1,380 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{prelude::FieldValue, EntityInstance, IntGridCell, LdtkEntity, LdtkIntCell};
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::{Collider, RapierContext};
+
+use crate::{
+    behaviour::navigation::NavGrid, entity::player::PlayerHurtboxDamage, PIXELS_PER_METER,
+};
+
+use super::{
+    level_manager::{entity_half_extents, LevelObject, Wall, WallColliderBundle},
+    GameState,
+};
+
+// Broken walls reveal secret areas they're this close to
+const SECRET_REVEAL_MARGIN: f32 = PIXELS_PER_METER * 2.0;
+
+const SECRET_FADE_SECONDS: f32 = 0.5;
+
+pub struct BreakablePlugin;
+
+impl Plugin for BreakablePlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_event::<PropBroken>()
+            .add_system(breakable_shape_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(breakable_damage_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(secret_area_shape_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(
+                secret_area_reveal_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(breakable_damage_system),
+            )
+            .add_system(secret_area_fade_system.in_set(OnUpdate(GameState::InLevel)));
+    }
+}
+
+// Walls and props the player can smash. `drop` is handed on in `PropBroken` for loot, and breaking
+// one opens up the `SecretArea` with the same `secret`
+#[derive(Component, Clone, Debug)]
+pub struct Breakable {
+    pub health: f32,
+    pub max_health: f32,
+    pub hit_cooldown: Timer,
+    pub drop: Option<String>,
+    pub secret: Option<String>,
+    pub half_extents: Vec2,
+}
+
+impl Default for Breakable {
+    fn default() -> Self {
+        // Ready to be hit straight away
+        let mut hit_cooldown = Timer::from_seconds(0.3, TimerMode::Once);
+        hit_cooldown.tick(hit_cooldown.duration());
+
+        Self {
+            health: 40.0,
+            max_health: 40.0,
+            hit_cooldown,
+            drop: None,
+            secret: None,
+            half_extents: Vec2::splat(PIXELS_PER_METER),
+        }
+    }
+}
+
+// Sent when a breakable's health runs out
+pub struct PropBroken {
+    pub position: Vec2,
+    pub drop: Option<String>,
+    pub secret: Option<String>,
+}
+
+// IntGrid walls that can be broken, drawn by the game like props since the layer has no tiles
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct BreakableWall {
+    #[from_int_grid_cell]
+    #[bundle]
+    pub wall_collider: WallColliderBundle,
+    #[from_int_grid_cell]
+    pub breakable: Breakable,
+}
+
+impl From<IntGridCell> for Breakable {
+    fn from(_int_grid_cell: IntGridCell) -> Breakable {
+        Breakable::default()
+    }
+}
+
+// Crates and the like placed as entities, drawn by the game
+#[derive(Component, Clone, Default, Debug)]
+pub struct BreakableProp;
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct BreakablePropEntityBundle {
+    #[from_entity_instance]
+    #[bundle]
+    pub prop: BreakablePropBundle,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct BreakablePropBundle {
+    pub breakable: Breakable,
+    pub prop: BreakableProp,
+    pub collider: Collider,
+    pub wall: Wall,
+    pub level_object: LevelObject,
+}
+
+// Hidden under a dark overlay until a breakable next to it, or with the same `secret`, is broken
+#[derive(Component, Clone, Default, Debug)]
+pub struct SecretArea {
+    pub secret: Option<String>,
+    pub half_extents: Vec2,
+    pub revealed: Option<Timer>,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct SecretAreaEntityBundle {
+    #[from_entity_instance]
+    #[bundle]
+    pub secret_area: SecretAreaBundle,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct SecretAreaBundle {
+    pub secret_area: SecretArea,
+    pub level_object: LevelObject,
+}
+
+#[derive(Component)]
+pub struct BreakableShape;
+
+#[derive(Component)]
+pub struct SecretAreaShape;
+
+fn find_field_value<'a>(
+    entity_instance: &'a EntityInstance,
+    field_identifier: &str,
+) -> Option<&'a FieldValue> {
+    entity_instance
+        .field_instances
+        .iter()
+        .find(|f| f.identifier.as_str() == field_identifier)
+        .map(|field_instance| &field_instance.value)
+}
+
+fn string_field(entity_instance: &EntityInstance, field_identifier: &str) -> Option<String> {
+    match find_field_value(entity_instance, field_identifier) {
+        Some(FieldValue::String(Some(value))) | Some(FieldValue::Enum(Some(value)))
+            if !value.is_empty() =>
+        {
+            Some(value.clone())
+        }
+        _ => None,
+    }
+}
+
+impl From<&EntityInstance> for BreakablePropBundle {
+    fn from(entity_instance: &EntityInstance) -> BreakablePropBundle {
+        // All fields are optional, a plain crate takes a couple of hits and drops nothing
+        let health = match find_field_value(entity_instance, "health") {
+            Some(FieldValue::Float(Some(health))) => health.max(1.0),
+            Some(FieldValue::Int(Some(health))) => (*health).max(1) as f32,
+            _ => 30.0,
+        };
+        let half_extents = entity_half_extents(entity_instance).max(Vec2::splat(1.0));
+
+        BreakablePropBundle {
+            breakable: Breakable {
+                health,
+                max_health: health,
+                drop: string_field(entity_instance, "drop"),
+                secret: string_field(entity_instance, "secret"),
+                half_extents,
+                ..default()
+            },
+            prop: BreakableProp,
+            collider: Collider::cuboid(half_extents.x, half_extents.y),
+            wall: Wall,
+            level_object: LevelObject,
+        }
+    }
+}
+
+impl From<&EntityInstance> for SecretAreaBundle {
+    fn from(entity_instance: &EntityInstance) -> SecretAreaBundle {
+        SecretAreaBundle {
+            secret_area: SecretArea {
+                secret: string_field(entity_instance, "secret"),
+                half_extents: entity_half_extents(entity_instance),
+                revealed: None,
+            },
+            level_object: LevelObject,
+        }
+    }
+}
+
+// Walls are stone, everything else is a wooden crate
+fn base_color(wall: bool) -> Color {
+    if wall {
+        Color::rgb(0.4, 0.4, 0.45)
+    } else {
+        Color::rgb(0.55, 0.35, 0.2)
+    }
+}
+
+fn rectangle(half_extents: Vec2) -> Path {
+    GeometryBuilder::build_as(&shapes::Rectangle {
+        extents: half_extents * 2.0,
+        origin: RectangleOrigin::Center,
+    })
+}
+
+pub fn breakable_shape_system(
+    mut commands: Commands,
+    breakable_query: Query<(Entity, &Breakable, Option<&IntGridCell>), Added<Breakable>>,
+) {
+    for (entity, breakable, int_grid_cell) in breakable_query.iter() {
+        let wall = int_grid_cell.is_some();
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                ShapeBundle {
+                    path: rectangle(breakable.half_extents),
+                    // The IntGrid layer is below the tiles, lift walls up to the entities
+                    transform: Transform::from_xyz(0.0, 0.0, if wall { 2.0 } else { 0.0 }),
+                    ..default()
+                },
+                Fill::color(base_color(wall)),
+                Stroke::new(base_color(wall) * 0.5, 2.0),
+                BreakableShape,
+            ));
+        });
+    }
+}
+
+pub fn breakable_damage_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut nav_grid: ResMut<NavGrid>,
+    mut prop_broken: EventWriter<PropBroken>,
+    mut breakable_query: Query<(
+        Entity,
+        &GlobalTransform,
+        &mut Breakable,
+        Option<&Children>,
+        Option<&IntGridCell>,
+    )>,
+    hurtbox_query: Query<(Entity, &PlayerHurtboxDamage)>,
+    mut shape_query: Query<(&mut Fill, &mut Path), With<BreakableShape>>,
+) {
+    for (entity, transform, mut breakable, children, int_grid_cell) in breakable_query.iter_mut() {
+        breakable.hit_cooldown.tick(time.delta());
+        let damage = if breakable.hit_cooldown.finished() {
+            hurtbox_query
+                .iter()
+                .filter(|(hurtbox, _)| {
+                    rapier_context.intersection_pair(entity, *hurtbox) == Some(true)
+                })
+                .map(|(_, damage)| damage.0 as f32)
+                .fold(0.0, f32::max)
+        } else {
+            0.0
+        };
+
+        let broken = if damage > 0.0 {
+            breakable.hit_cooldown.reset();
+            breakable.health -= damage;
+            breakable.health <= 0.0
+        } else {
+            false
+        };
+
+        // Props flash while they can't be hit again, and are left as a pile of debris
+        let flash = !breakable.hit_cooldown.finished();
+        for child in children.into_iter().flatten() {
+            if let Ok((mut fill, mut path)) = shape_query.get_mut(*child) {
+                if broken {
+                    fill.color = Color::rgba(0.35, 0.22, 0.12, 0.7);
+                    *path = rectangle(breakable.half_extents * Vec2::new(0.8, 0.3));
+                } else if flash {
+                    fill.color = Color::rgb(0.9, 0.8, 0.7);
+                } else {
+                    fill.color = base_color(int_grid_cell.is_some());
+                }
+            }
+        }
+        if !broken {
+            continue;
+        }
+
+        let position = transform.translation().truncate();
+        prop_broken.send(PropBroken {
+            position,
+            drop: breakable.drop.clone(),
+            secret: breakable.secret.clone(),
+        });
+        nav_grid.open_cell(position);
+
+        // Walls are the tile itself, so they go completely, props stay as debris
+        if int_grid_cell.is_some() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            commands
+                .entity(entity)
+                .remove::<(Breakable, Collider, Wall)>();
+        }
+    }
+}
+
+// The overlay hiding what's in the secret area
+pub fn secret_area_shape_system(
+    mut commands: Commands,
+    secret_query: Query<(Entity, &SecretArea), Added<SecretArea>>,
+) {
+    for (entity, secret_area) in secret_query.iter() {
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                ShapeBundle {
+                    path: rectangle(secret_area.half_extents),
+                    // Above creatures, so whatever is inside stays hidden
+                    transform: Transform::from_xyz(0.0, 0.0, 50.0),
+                    ..default()
+                },
+                Fill::color(Color::rgb(0.05, 0.05, 0.08)),
+                SecretAreaShape,
+            ));
+        });
+    }
+}
+
+pub fn secret_area_reveal_system(
+    mut prop_broken: EventReader<PropBroken>,
+    mut secret_query: Query<(&GlobalTransform, &mut SecretArea)>,
+) {
+    for broken in prop_broken.iter() {
+        for (transform, mut secret_area) in secret_query.iter_mut() {
+            if secret_area.revealed.is_some() {
+                continue;
+            }
+
+            let linked = match (&broken.secret, &secret_area.secret) {
+                (Some(broken_secret), Some(secret)) => broken_secret == secret,
+                _ => false,
+            };
+            let offset = (broken.position - transform.translation().truncate()).abs();
+            let margin = secret_area.half_extents + Vec2::splat(SECRET_REVEAL_MARGIN);
+            let adjacent = offset.x <= margin.x && offset.y <= margin.y;
+
+            if linked || adjacent {
+                secret_area.revealed =
+                    Some(Timer::from_seconds(SECRET_FADE_SECONDS, TimerMode::Once));
+            }
+        }
+    }
+}
+
+pub fn secret_area_fade_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut secret_query: Query<(Entity, &mut SecretArea, &Children)>,
+    mut shape_query: Query<&mut Fill, With<SecretAreaShape>>,
+) {
+    for (entity, mut secret_area, children) in secret_query.iter_mut() {
+        let timer = match secret_area.revealed.as_mut() {
+            Some(timer) => timer,
+            None => continue,
+        };
+        timer.tick(time.delta());
+
+        for child in children.iter() {
+            if let Ok(mut fill) = shape_query.get_mut(*child) {
+                fill.color.set_a(timer.percent_left());
+            }
+        }
+        if timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
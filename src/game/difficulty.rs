@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use crate::{
+    app_state::AppState,
+    behaviour::separation::Separation,
+    entity::{creature::Creature, faction::Faction, spawner::Spawner, Enemy, EnemyHurtboxDamage},
+};
+
+use super::level_manager::LevelManager;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Difficulty>()
+            .add_system(scale_spawned_enemies.in_set(OnUpdate(AppState::InGame)))
+            .add_system(scale_enemy_hurtbox_damage.in_set(OnUpdate(AppState::InGame)))
+            .add_system(scale_spawners.in_set(OnUpdate(AppState::InGame)))
+            .add_system(difficulty_cleanup.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub enum DifficultyPreset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl DifficultyPreset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DifficultyPreset::Easy => "Easy",
+            DifficultyPreset::Normal => "Normal",
+            DifficultyPreset::Hard => "Hard",
+        }
+    }
+
+    fn curves(&self) -> DifficultyCurves {
+        match self {
+            DifficultyPreset::Easy => DifficultyCurves {
+                health: DifficultyCurve::new(0.75, 0.05),
+                speed: DifficultyCurve::new(0.9, 0.01),
+                damage: DifficultyCurve::new(0.6, 0.04),
+                spawn_count: DifficultyCurve::new(0.75, 0.03),
+                elite_chance: DifficultyCurve::new(0.5, 0.1),
+            },
+            DifficultyPreset::Normal => DifficultyCurves {
+                health: DifficultyCurve::new(1.0, 0.08),
+                speed: DifficultyCurve::new(1.0, 0.02),
+                damage: DifficultyCurve::new(1.0, 0.06),
+                spawn_count: DifficultyCurve::new(1.0, 0.05),
+                elite_chance: DifficultyCurve::new(1.0, 0.2),
+            },
+            DifficultyPreset::Hard => DifficultyCurves {
+                health: DifficultyCurve::new(1.3, 0.1),
+                speed: DifficultyCurve::new(1.1, 0.03),
+                damage: DifficultyCurve::new(1.4, 0.08),
+                spawn_count: DifficultyCurve::new(1.3, 0.06),
+                elite_chance: DifficultyCurve::new(2.0, 0.3),
+            },
+        }
+    }
+}
+
+// A multiplier that starts at `base` on the first level and grows linearly with each level cleared
+#[derive(Debug, Clone, Copy)]
+struct DifficultyCurve {
+    base: f32,
+    per_level: f32,
+}
+
+impl DifficultyCurve {
+    fn new(base: f32, per_level: f32) -> Self {
+        Self { base, per_level }
+    }
+
+    fn at(&self, levels_cleared: usize) -> f32 {
+        self.base + self.per_level * levels_cleared as f32
+    }
+}
+
+struct DifficultyCurves {
+    health: DifficultyCurve,
+    speed: DifficultyCurve,
+    damage: DifficultyCurve,
+    spawn_count: DifficultyCurve,
+    elite_chance: DifficultyCurve,
+}
+
+// Multipliers applied to enemy stats when they are spawned
+#[derive(Debug, Clone, Copy)]
+pub struct DifficultyScaling {
+    pub health: f32,
+    pub speed: f32,
+    pub damage: f32,
+    pub spawn_count: f32,
+    pub elite_chance: f32,
+}
+
+#[derive(Resource, Reflect, Default)]
+pub struct Difficulty {
+    pub preset: DifficultyPreset,
+    // How many times the player has beaten the game this run
+    pub new_game_plus: u32,
+}
+
+impl Difficulty {
+    pub fn scaling(&self, levels_cleared: usize) -> DifficultyScaling {
+        let curves = self.preset.curves();
+        let loops = self.new_game_plus as f32;
+        DifficultyScaling {
+            health: curves.health.at(levels_cleared) * (1.0 + loops * 0.5),
+            speed: curves.speed.at(levels_cleared) * (1.0 + loops * 0.1),
+            damage: curves.damage.at(levels_cleared) * (1.0 + loops * 0.5),
+            spawn_count: curves.spawn_count.at(levels_cleared) * (1.0 + loops * 0.5),
+            elite_chance: curves.elite_chance.at(levels_cleared) * (1.0 + loops),
+        }
+    }
+
+    pub fn next_preset(&mut self) {
+        self.preset = match self.preset {
+            DifficultyPreset::Easy => DifficultyPreset::Normal,
+            DifficultyPreset::Normal => DifficultyPreset::Hard,
+            DifficultyPreset::Hard => DifficultyPreset::Easy,
+        };
+    }
+}
+
+fn current_scaling(
+    difficulty: &Difficulty,
+    level_manager: Option<Res<LevelManager>>,
+) -> DifficultyScaling {
+    let levels_cleared = level_manager
+        .map(|level_manager| level_manager.levels_cleared())
+        .unwrap_or(0);
+    difficulty.scaling(levels_cleared)
+}
+
+pub fn scale_spawned_enemies(
+    difficulty: Res<Difficulty>,
+    level_manager: Option<Res<LevelManager>>,
+    mut query: Query<(&mut Creature, Option<&mut Separation>, Option<&Faction>), Added<Enemy>>,
+) {
+    let scaling = current_scaling(&difficulty, level_manager);
+    for (mut creature, separation, faction) in query.iter_mut() {
+        // Friendly summons and charmed spawns fight for the player, they don't get tougher
+        if faction == Some(&Faction::Player) {
+            continue;
+        }
+
+        creature.max_health *= scaling.health;
+        creature.health = creature.max_health;
+        creature.max_speed *= scaling.speed;
+        creature.acceleration *= scaling.speed;
+
+        // Separation resets the max speed once enemies are no longer crowded
+        if let Some(mut separation) = separation {
+            if let Some(max_speed_reset) = separation.max_speed_reset {
+                separation.max_speed_reset = Some(max_speed_reset * scaling.speed);
+            }
+        }
+    }
+}
+
+// Covers enemy bodies as well as projectiles and attack hurtboxes
+pub fn scale_enemy_hurtbox_damage(
+    difficulty: Res<Difficulty>,
+    level_manager: Option<Res<LevelManager>>,
+    mut query: Query<(&mut EnemyHurtboxDamage, Option<&Faction>), Added<EnemyHurtboxDamage>>,
+) {
+    let scaling = current_scaling(&difficulty, level_manager);
+    for (mut damage, faction) in query.iter_mut() {
+        if faction == Some(&Faction::Player) {
+            continue;
+        }
+        damage.0 = ((damage.0 as f32 * scaling.damage).round() as u32).max(1);
+    }
+}
+
+pub fn scale_spawners(
+    difficulty: Res<Difficulty>,
+    level_manager: Option<Res<LevelManager>>,
+    mut query: Query<&mut Spawner, Added<Spawner>>,
+) {
+    let scaling = current_scaling(&difficulty, level_manager);
+    for mut spawner in query.iter_mut() {
+        spawner.spawn_count =
+            ((spawner.spawn_count as f32 * scaling.spawn_count).round() as usize).max(1);
+        // Summons and splits have no elite chance to begin with, so they stay regular enemies
+        spawner.elite_chance = (spawner.elite_chance * scaling.elite_chance).min(1.0);
+    }
+}
+
+fn difficulty_cleanup(mut difficulty: ResMut<Difficulty>) {
+    difficulty.new_game_plus = 0;
+}
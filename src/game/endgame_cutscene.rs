@@ -1,14 +1,17 @@
 use bevy::prelude::*;
 use bevy_kira_audio::AudioChannel;
 
-use crate::app_state::{
-    loading::{Background, CutsceneAssets, MusicAssets},
-    AppState,
+use crate::{
+    app_state::{
+        loading::{Background, CutsceneAssets, MusicAssets},
+        AppState,
+    },
+    entity::{creature::Creature, player::Player},
 };
 
 use bevy_kira_audio::AudioControl;
 
-use super::GameState;
+use super::{difficulty::Difficulty, GameState};
 
 pub struct EndgameCutscenePlugin;
 
@@ -31,8 +34,12 @@ pub struct EndgameCutsceneData {
 #[derive(Component)]
 pub struct EndgameCutsceneImageNode;
 
+#[derive(Component)]
+pub struct NewGamePlusPrompt;
+
 pub fn endgame_cutscene_setup(
     mut commands: Commands,
+    asset_server: Res<AssetServer>,
     cutscene_assets: Res<CutsceneAssets>,
     background: Res<AudioChannel<Background>>,
     music_assets: Res<MusicAssets>,
@@ -79,6 +86,28 @@ pub fn endgame_cutscene_setup(
                         ..default()
                     });
                 });
+            // Shown once the cutscene is over
+            parent.spawn((
+                TextBundle::from_section(
+                    "Space: New Game+    Esc: Main menu",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(20.0),
+                        right: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                }),
+                Visibility::Hidden,
+                NewGamePlusPrompt,
+            ));
         })
         .id();
     commands.insert_resource(EndgameCutsceneData {
@@ -92,6 +121,11 @@ pub fn endgame_cutscene_setup(
 pub fn endgame_cutscene_system(
     _commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut prompt_query: Query<&mut Visibility, With<NewGamePlusPrompt>>,
+    mut player_query: Query<&mut Creature, With<Player>>,
+    mut difficulty: ResMut<Difficulty>,
     mut cutscene_image_node_query: Query<&mut Style, With<EndgameCutsceneImageNode>>,
     mut cutscene_data: ResMut<EndgameCutsceneData>,
     mut cutscene_image: Query<&mut UiImage>,
@@ -131,7 +165,20 @@ pub fn endgame_cutscene_system(
             && cutscene_data.cutscene_timer_2.finished()
             && cutscene_data.cutscene_timer_3.finished()
         {
-            next_state.set(AppState::MainMenu);
+            for mut visibility in prompt_query.iter_mut() {
+                *visibility = Visibility::Visible;
+            }
+
+            // New Game+ goes straight back to the first level, keeping mutations but not damage
+            if keyboard_input.just_pressed(KeyCode::Space) {
+                difficulty.new_game_plus += 1;
+                for mut creature in player_query.iter_mut() {
+                    creature.health = creature.max_health;
+                }
+                next_game_state.set(GameState::SetupLevelManager);
+            } else if keyboard_input.just_pressed(KeyCode::Escape) {
+                next_state.set(AppState::MainMenu);
+            }
         }
     }
 }
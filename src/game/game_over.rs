@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+
+use crate::{
+    app_state::AppState,
+    entity::{
+        creature::{Creature, Lifetime},
+        player::{Dead, Player},
+        spawner::Spawner,
+        Enemy,
+    },
+};
+
+use super::{
+    arena::Arena,
+    level_manager::{LevelManager, LevelObject},
+    mutation_manager::MutationManager,
+    run_stats::RunStats,
+    GameMode, GameState,
+};
+
+// How long the player takes to keel over before the summary shows up
+const DEATH_ANIMATION_SECONDS: f32 = 1.5;
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(game_over_setup.in_schedule(OnEnter(GameState::GameOver)))
+            .add_system(player_death_animation.in_set(OnUpdate(GameState::GameOver)))
+            .add_system(game_over_system.in_set(OnUpdate(GameState::GameOver)))
+            .add_system(game_over_cleanup.in_schedule(OnExit(GameState::GameOver)));
+    }
+}
+
+#[derive(Resource)]
+pub struct GameOverData {
+    pub ui_entity: Entity,
+    pub death_timer: Timer,
+}
+
+#[derive(Component)]
+pub struct GameOverSummary;
+
+fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub fn game_over_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    arena: Option<Res<Arena>>,
+    level_manager: Option<Res<LevelManager>>,
+    mutation_manager: Res<MutationManager>,
+    run_stats: Res<RunStats>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let mut lines = vec![("Game Over".to_string(), 80.0)];
+    if let Some(arena) = arena {
+        lines.push((format!("Score: {}", arena.final_score()), 40.0));
+    } else if let Some(level_manager) = level_manager {
+        lines.push((
+            format!("Levels cleared: {}", level_manager.levels_cleared()),
+            40.0,
+        ));
+    }
+    lines.push((format!("Time: {}", format_time(run_stats.time)), 40.0));
+
+    let mutations: Vec<String> = mutation_manager
+        .player_mutations
+        .iter()
+        .map(|mutation| format!("{:?}", mutation.mutation_type))
+        .collect();
+    let mutations = if mutations.is_empty() {
+        "None".to_string()
+    } else {
+        mutations.join(", ")
+    };
+    lines.push((format!("Mutations: {}", mutations), 30.0));
+
+    lines.push((format!("Kills: {}", run_stats.total_kills()), 40.0));
+    for (enemy_type, kills) in run_stats.kills.iter() {
+        lines.push((format!("{} x{}", enemy_type, kills), 30.0));
+    }
+    lines.push(("R: Retry level    Space: Main menu".to_string(), 30.0));
+
+    let ui_entity = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                // Hidden until the death animation is over
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            GameOverSummary,
+        ))
+        .with_children(|parent| {
+            for (line, font_size) in lines {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size,
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+        })
+        .id();
+    commands.insert_resource(GameOverData {
+        ui_entity,
+        death_timer: Timer::from_seconds(DEATH_ANIMATION_SECONDS, TimerMode::Once),
+    });
+}
+
+// Tip the player over and fade them out
+pub fn player_death_animation(
+    time: Res<Time>,
+    mut game_over_data: ResMut<GameOverData>,
+    mut player_query: Query<(&mut Transform, &mut TextureAtlasSprite), With<Player>>,
+    mut summary_query: Query<&mut Visibility, With<GameOverSummary>>,
+) {
+    game_over_data.death_timer.tick(time.delta());
+    let progress = game_over_data.death_timer.percent();
+
+    for (mut transform, mut sprite) in player_query.iter_mut() {
+        let direction = if sprite.flip_x { -1.0 } else { 1.0 };
+        transform.rotation =
+            Quat::from_rotation_z(direction * std::f32::consts::FRAC_PI_2 * progress);
+        sprite.color = Color::rgba(1.0, 1.0 - progress, 1.0 - progress, 1.0 - progress * 0.6);
+    }
+
+    if game_over_data.death_timer.just_finished() {
+        for mut visibility in summary_query.iter_mut() {
+            *visibility = Visibility::Visible;
+        }
+    }
+}
+
+pub fn game_over_system(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    game_over_data: Res<GameOverData>,
+    game_mode: Res<GameMode>,
+    level_manager: Option<ResMut<LevelManager>>,
+    mut player_query: Query<&mut Creature, With<Player>>,
+    clear_query: Query<
+        Entity,
+        Or<(
+            With<Enemy>,
+            With<Spawner>,
+            With<LevelObject>,
+            With<Lifetime>,
+        )>,
+    >,
+) {
+    if !game_over_data.death_timer.finished() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        next_state.set(AppState::MainMenu);
+    } else if keyboard_input.just_pressed(KeyCode::R) {
+        // Clear out what's left of the level, level setup spawns it again from scratch
+        for entity in clear_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        for mut creature in player_query.iter_mut() {
+            creature.health = creature.max_health;
+        }
+
+        if *game_mode == GameMode::Arena {
+            // Arena runs start over with a fresh score
+            next_game_state.set(GameState::SetupLevelManager);
+        } else if let Some(mut level_manager) = level_manager {
+            // Going back to the current level shouldn't count it as cleared.
+            // Generated levels get a new layout
+            level_manager.next_level = level_manager.current_level.take();
+            next_game_state.set(GameState::SetupLevel);
+        }
+    }
+}
+
+pub fn game_over_cleanup(
+    mut commands: Commands,
+    game_over_data: Res<GameOverData>,
+    mut player_query: Query<(Entity, &mut Transform, &mut TextureAtlasSprite), With<Player>>,
+) {
+    commands
+        .entity(game_over_data.ui_entity)
+        .despawn_recursive();
+
+    for (entity, mut transform, mut sprite) in player_query.iter_mut() {
+        transform.rotation = Quat::IDENTITY;
+        sprite.color = Color::WHITE;
+        commands.entity(entity).remove::<Dead>();
+    }
+}
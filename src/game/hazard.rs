@@ -0,0 +1,549 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{prelude::FieldValue, EntityInstance, LdtkEntity};
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::{ActiveCollisionTypes, Collider, RapierContext, Sensor};
+
+use crate::{
+    entity::{
+        creature::{Bleed, Creature, DealDamage, Lifetime, Velocity},
+        faction::Faction,
+        player::{Immune, PlayerHurtboxDamage},
+        EnemyHurtboxDamage,
+    },
+    PIXELS_PER_METER,
+};
+
+use super::{
+    level_manager::{entity_half_extents, LevelObject},
+    GameState,
+};
+
+// How quickly conveyors get creatures up to speed, on top of cancelling out their friction
+const CONVEYOR_ACCELERATION: f32 = 256.0;
+
+// Barrels caught in an explosion go off shortly after, so chains ripple outwards
+const CHAIN_FUSE_SECONDS: f32 = 0.15;
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.add_system(hazard_shape_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(spike_floor_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(acid_pool_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(explosive_barrel_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(conveyor_system.in_set(OnUpdate(GameState::InLevel)));
+    }
+}
+
+// Retracts and extends on a loop, hurting anything standing on it while it's up
+#[derive(Component, Clone, Default, Debug)]
+pub struct SpikeFloor {
+    pub damage: u32,
+    pub up_seconds: f32,
+    pub down_seconds: f32,
+    pub half_extents: Vec2,
+    pub timer: Timer,
+    pub up: bool,
+}
+
+// Makes whatever walks through it bleed
+#[derive(Component, Clone, Default, Debug)]
+pub struct AcidPool {
+    pub damage: f32,
+    pub ticks: u32,
+    pub tick_seconds: f32,
+    pub half_extents: Vec2,
+}
+
+// Goes off after enough hits from attacks or projectiles, hurting and knocking back everything in
+// the radius and setting off other barrels
+#[derive(Component, Clone, Default, Debug)]
+pub struct ExplosiveBarrel {
+    pub damage: f32,
+    pub radius: f32,
+    pub knockback: f32,
+    pub hits: u32,
+    pub hit_cooldown: Timer,
+    pub fuse: Option<Timer>,
+}
+
+// Pushes creatures on it along at up to `speed`
+#[derive(Component, Clone, Default, Debug)]
+pub struct Conveyor {
+    pub direction: Vec2,
+    pub speed: f32,
+    pub half_extents: Vec2,
+}
+
+// The lyon shape drawn for a hazard, as a child so it doesn't replace the LDtk transform
+#[derive(Component)]
+pub struct HazardShape;
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct SpikeFloorEntityBundle {
+    #[from_entity_instance]
+    #[bundle]
+    pub spike_floor: SpikeFloorBundle,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct SpikeFloorBundle {
+    pub spike_floor: SpikeFloor,
+    pub faction: Faction,
+    pub collider: Collider,
+    pub sensor: Sensor,
+    pub collision_types: ActiveCollisionTypes,
+    pub level_object: LevelObject,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct AcidPoolEntityBundle {
+    #[from_entity_instance]
+    #[bundle]
+    pub acid_pool: AcidPoolBundle,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct AcidPoolBundle {
+    pub acid_pool: AcidPool,
+    pub level_object: LevelObject,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct ExplosiveBarrelEntityBundle {
+    #[from_entity_instance]
+    #[bundle]
+    pub explosive_barrel: ExplosiveBarrelBundle,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct ExplosiveBarrelBundle {
+    pub explosive_barrel: ExplosiveBarrel,
+    pub collider: Collider,
+    pub sensor: Sensor,
+    pub collision_types: ActiveCollisionTypes,
+    pub level_object: LevelObject,
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct ConveyorEntityBundle {
+    #[from_entity_instance]
+    #[bundle]
+    pub conveyor: ConveyorBundle,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct ConveyorBundle {
+    pub conveyor: Conveyor,
+    pub level_object: LevelObject,
+}
+
+fn find_field_value<'a>(
+    entity_instance: &'a EntityInstance,
+    field_identifier: &str,
+) -> Option<&'a FieldValue> {
+    entity_instance
+        .field_instances
+        .iter()
+        .find(|f| f.identifier.as_str() == field_identifier)
+        .map(|field_instance| &field_instance.value)
+}
+
+fn float_field(entity_instance: &EntityInstance, field_identifier: &str, default: f32) -> f32 {
+    match find_field_value(entity_instance, field_identifier) {
+        Some(FieldValue::Float(Some(value))) => *value,
+        _ => default,
+    }
+}
+
+fn int_field(entity_instance: &EntityInstance, field_identifier: &str, default: i32) -> i32 {
+    match find_field_value(entity_instance, field_identifier) {
+        Some(FieldValue::Int(Some(value))) => *value,
+        _ => default,
+    }
+}
+
+fn contains(center: Vec2, half_extents: Vec2, point: Vec2) -> bool {
+    let offset = (point - center).abs();
+    offset.x <= half_extents.x && offset.y <= half_extents.y
+}
+
+// All fields are optional, every hazard works with just its size set in LDtk
+impl From<&EntityInstance> for SpikeFloorBundle {
+    fn from(entity_instance: &EntityInstance) -> SpikeFloorBundle {
+        let half_extents = entity_half_extents(entity_instance);
+        let up_seconds = float_field(entity_instance, "up_seconds", 1.0).max(0.1);
+        let down_seconds = float_field(entity_instance, "down_seconds", 2.0).max(0.1);
+        // Staggers neighbouring spikes
+        let offset = float_field(entity_instance, "offset", 0.0).max(0.0);
+
+        let mut timer = Timer::from_seconds(down_seconds, TimerMode::Once);
+        timer.tick(std::time::Duration::from_secs_f32(offset.min(down_seconds)));
+
+        SpikeFloorBundle {
+            spike_floor: SpikeFloor {
+                damage: int_field(entity_instance, "damage", 8).max(0) as u32,
+                up_seconds,
+                down_seconds,
+                half_extents,
+                timer,
+                up: false,
+            },
+            faction: Faction::Neutral,
+            collider: Collider::cuboid(half_extents.x, half_extents.y),
+            sensor: Sensor,
+            collision_types: ActiveCollisionTypes::STATIC_STATIC,
+            level_object: LevelObject,
+        }
+    }
+}
+
+impl From<&EntityInstance> for AcidPoolBundle {
+    fn from(entity_instance: &EntityInstance) -> AcidPoolBundle {
+        AcidPoolBundle {
+            acid_pool: AcidPool {
+                damage: float_field(entity_instance, "damage", 2.0).max(0.0),
+                ticks: int_field(entity_instance, "ticks", 3).max(1) as u32,
+                tick_seconds: float_field(entity_instance, "tick_seconds", 1.0).max(0.1),
+                half_extents: entity_half_extents(entity_instance),
+            },
+            level_object: LevelObject,
+        }
+    }
+}
+
+impl From<&EntityInstance> for ExplosiveBarrelBundle {
+    fn from(entity_instance: &EntityInstance) -> ExplosiveBarrelBundle {
+        let size = entity_half_extents(entity_instance).min_element();
+        let size = if size > 0.0 {
+            size
+        } else {
+            PIXELS_PER_METER * 0.5
+        };
+
+        ExplosiveBarrelBundle {
+            explosive_barrel: ExplosiveBarrel {
+                damage: float_field(entity_instance, "damage", 25.0).max(0.0),
+                // In meters in LDtk
+                radius: float_field(entity_instance, "radius", 2.5).max(0.5) * PIXELS_PER_METER,
+                knockback: float_field(entity_instance, "knockback", 400.0).max(0.0),
+                hits: int_field(entity_instance, "hits", 1).max(1) as u32,
+                hit_cooldown: Timer::from_seconds(0.25, TimerMode::Once),
+                fuse: None,
+            },
+            collider: Collider::ball(size),
+            sensor: Sensor,
+            collision_types: ActiveCollisionTypes::STATIC_STATIC,
+            level_object: LevelObject,
+        }
+    }
+}
+
+impl From<&EntityInstance> for ConveyorBundle {
+    fn from(entity_instance: &EntityInstance) -> ConveyorBundle {
+        let direction = match find_field_value(entity_instance, "direction") {
+            Some(FieldValue::Enum(Some(direction))) | Some(FieldValue::String(Some(direction))) => {
+                match direction.as_str() {
+                    "Up" => Vec2::Y,
+                    "Down" => Vec2::NEG_Y,
+                    "Left" => Vec2::NEG_X,
+                    _ => Vec2::X,
+                }
+            }
+            _ => Vec2::X,
+        };
+
+        ConveyorBundle {
+            conveyor: Conveyor {
+                direction,
+                speed: float_field(entity_instance, "speed", 96.0).max(0.0),
+                half_extents: entity_half_extents(entity_instance),
+            },
+            level_object: LevelObject,
+        }
+    }
+}
+
+// Draws every hazard once it's been spawned from LDtk
+pub fn hazard_shape_system(
+    mut commands: Commands,
+    spike_query: Query<(Entity, &SpikeFloor), Added<SpikeFloor>>,
+    acid_query: Query<(Entity, &AcidPool), Added<AcidPool>>,
+    barrel_query: Query<(Entity, &Collider), Added<ExplosiveBarrel>>,
+    conveyor_query: Query<(Entity, &Conveyor), Added<Conveyor>>,
+) {
+    let rectangle = |half_extents: Vec2| {
+        GeometryBuilder::build_as(&shapes::Rectangle {
+            extents: half_extents * 2.0,
+            origin: RectangleOrigin::Center,
+        })
+    };
+    let mut spawn_shape = |entity: Entity, path: Path, fill: Color| {
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                ShapeBundle {
+                    path,
+                    // Floor hazards sit under the creatures walking over them
+                    transform: Transform::from_xyz(0.0, 0.0, -10.0),
+                    ..default()
+                },
+                Fill::color(fill),
+                HazardShape,
+            ));
+        });
+    };
+
+    for (entity, spike_floor) in spike_query.iter() {
+        spawn_shape(
+            entity,
+            rectangle(spike_floor.half_extents),
+            Color::rgba(0.4, 0.4, 0.45, 0.5),
+        );
+    }
+    for (entity, acid_pool) in acid_query.iter() {
+        spawn_shape(
+            entity,
+            rectangle(acid_pool.half_extents),
+            Color::rgba(0.5, 1.0, 0.1, 0.45),
+        );
+    }
+    for (entity, collider) in barrel_query.iter() {
+        let radius = collider
+            .as_ball()
+            .map(|ball| ball.radius())
+            .unwrap_or(PIXELS_PER_METER * 0.5);
+        spawn_shape(
+            entity,
+            GeometryBuilder::build_as(&shapes::Circle {
+                radius,
+                center: Vec2::ZERO,
+            }),
+            Color::rgb(0.8, 0.25, 0.1),
+        );
+    }
+    for (entity, conveyor) in conveyor_query.iter() {
+        spawn_shape(
+            entity,
+            rectangle(conveyor.half_extents),
+            Color::rgba(0.3, 0.3, 0.35, 0.6),
+        );
+        // An arrow showing which way it goes
+        let size = conveyor.half_extents.min_element() * 0.6;
+        let forward = conveyor.direction * size;
+        let side = conveyor.direction.perp() * size * 0.6;
+        spawn_shape(
+            entity,
+            GeometryBuilder::build_as(&shapes::Polygon {
+                points: vec![forward, -forward * 0.5 + side, -forward * 0.5 - side],
+                closed: true,
+            }),
+            Color::rgba(0.9, 0.8, 0.2, 0.6),
+        );
+    }
+}
+
+fn set_shape_color(
+    children: &Children,
+    shape_query: &mut Query<&mut Fill, With<HazardShape>>,
+    color: Color,
+) {
+    for child in children.iter() {
+        if let Ok(mut fill) = shape_query.get_mut(*child) {
+            fill.color = color;
+        }
+    }
+}
+
+// Spikes are a neutral hurtbox while they're up, so hit resolution hurts whoever is on them
+pub fn spike_floor_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut spike_query: Query<(Entity, &mut SpikeFloor, Option<&Children>)>,
+    mut shape_query: Query<&mut Fill, With<HazardShape>>,
+) {
+    for (entity, mut spike_floor, children) in spike_query.iter_mut() {
+        spike_floor.timer.tick(time.delta());
+
+        if spike_floor.timer.finished() {
+            spike_floor.up = !spike_floor.up;
+            let duration = if spike_floor.up {
+                commands
+                    .entity(entity)
+                    .insert(EnemyHurtboxDamage(spike_floor.damage));
+                spike_floor.up_seconds
+            } else {
+                commands.entity(entity).remove::<EnemyHurtboxDamage>();
+                spike_floor.down_seconds
+            };
+            spike_floor.timer = Timer::from_seconds(duration, TimerMode::Once);
+        }
+
+        // Rattles right before coming up
+        let warning = !spike_floor.up && spike_floor.timer.remaining_secs() < 0.3;
+        let color = if spike_floor.up {
+            Color::rgba(0.85, 0.85, 0.9, 0.9)
+        } else if warning && (spike_floor.timer.elapsed_secs() * 20.0) as u32 % 2 == 0 {
+            Color::rgba(0.6, 0.6, 0.65, 0.7)
+        } else {
+            Color::rgba(0.4, 0.4, 0.45, 0.5)
+        };
+        if let Some(children) = children {
+            set_shape_color(children, &mut shape_query, color);
+        }
+    }
+}
+
+pub fn acid_pool_system(
+    mut commands: Commands,
+    acid_query: Query<(&GlobalTransform, &AcidPool)>,
+    creature_query: Query<(Entity, &Transform, Option<&Immune>), (With<Creature>, Without<Bleed>)>,
+) {
+    for (acid_transform, acid_pool) in acid_query.iter() {
+        let center = acid_transform.translation().truncate();
+        for (entity, transform, immune) in creature_query.iter() {
+            if immune.is_some()
+                || !contains(
+                    center,
+                    acid_pool.half_extents,
+                    transform.translation.truncate(),
+                )
+            {
+                continue;
+            }
+            commands.entity(entity).insert(Bleed {
+                damage: acid_pool.damage,
+                ticks: acid_pool.ticks,
+                tick_timer: Timer::from_seconds(acid_pool.tick_seconds, TimerMode::Once),
+            });
+        }
+    }
+}
+
+pub fn explosive_barrel_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    mut barrel_query: Query<(
+        Entity,
+        &GlobalTransform,
+        &mut ExplosiveBarrel,
+        Option<&Children>,
+    )>,
+    // Attacks and projectiles, not creatures bumping into the barrel
+    hurtbox_query: Query<
+        Entity,
+        (
+            Or<(With<PlayerHurtboxDamage>, With<EnemyHurtboxDamage>)>,
+            Without<Creature>,
+        ),
+    >,
+    creature_query: Query<(Entity, &Transform, Option<&Immune>), With<Creature>>,
+    mut shape_query: Query<&mut Fill, With<HazardShape>>,
+) {
+    let mut explosions = Vec::new();
+
+    for (entity, transform, mut barrel, children) in barrel_query.iter_mut() {
+        barrel.hit_cooldown.tick(time.delta());
+
+        if barrel.fuse.is_none()
+            && barrel.hit_cooldown.finished()
+            && hurtbox_query
+                .iter()
+                .any(|hurtbox| rapier_context.intersection_pair(entity, hurtbox) == Some(true))
+        {
+            barrel.hit_cooldown.reset();
+            barrel.hits = barrel.hits.saturating_sub(1);
+            if barrel.hits == 0 {
+                barrel.fuse = Some(Timer::from_seconds(CHAIN_FUSE_SECONDS, TimerMode::Once));
+            }
+        }
+
+        let exploded = match barrel.fuse.as_mut() {
+            Some(fuse) => fuse.tick(time.delta()).finished(),
+            None => continue,
+        };
+
+        // Flash while the fuse burns
+        if let Some(children) = children {
+            set_shape_color(children, &mut shape_query, Color::rgb(1.0, 0.9, 0.4));
+        }
+
+        if exploded {
+            explosions.push((entity, transform.translation().truncate(), barrel.clone()));
+        }
+    }
+
+    for (entity, center, barrel) in explosions.iter() {
+        for (creature, transform, immune) in creature_query.iter() {
+            let offset = transform.translation.truncate() - *center;
+            // Rolling through a blast is fine
+            if immune.is_some() || offset.length() > barrel.radius {
+                continue;
+            }
+            commands.entity(creature).insert(DealDamage {
+                amount: barrel.damage,
+                knockback_direction: offset.normalize_or_zero(),
+                knockback_force: barrel.knockback,
+            });
+        }
+
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shapes::Circle {
+                    radius: barrel.radius,
+                    center: Vec2::ZERO,
+                }),
+                transform: Transform::from_translation(center.extend(1.0)),
+                ..default()
+            },
+            Fill::color(Color::rgba(1.0, 0.5, 0.1, 0.5)),
+            Lifetime {
+                timer: Timer::from_seconds(0.25, TimerMode::Once),
+            },
+            LevelObject,
+        ));
+        commands.entity(*entity).despawn_recursive();
+    }
+
+    // Light the fuse of every barrel caught in a blast
+    for (entity, transform, mut barrel, _) in barrel_query.iter_mut() {
+        if barrel.fuse.is_some() {
+            continue;
+        }
+        let position = transform.translation().truncate();
+        if explosions.iter().any(|(exploded, center, explosion)| {
+            *exploded != entity && position.distance(*center) <= explosion.radius
+        }) {
+            barrel.fuse = Some(Timer::from_seconds(CHAIN_FUSE_SECONDS, TimerMode::Once));
+        }
+    }
+}
+
+pub fn conveyor_system(
+    time: Res<Time>,
+    conveyor_query: Query<(&GlobalTransform, &Conveyor)>,
+    mut creature_query: Query<(&Transform, &Creature, &mut Velocity)>,
+) {
+    for (conveyor_transform, conveyor) in conveyor_query.iter() {
+        let center = conveyor_transform.translation().truncate();
+        for (transform, creature, mut velocity) in creature_query.iter_mut() {
+            if !contains(
+                center,
+                conveyor.half_extents,
+                transform.translation.truncate(),
+            ) {
+                continue;
+            }
+
+            // Only pushes up to the conveyor's speed, moving with it on top of that is up to them
+            let along = velocity.value.dot(conveyor.direction);
+            if along >= conveyor.speed {
+                continue;
+            }
+            let push = ((creature.friction + CONVEYOR_ACCELERATION) * time.delta_seconds())
+                .min(conveyor.speed - along);
+            velocity.value += conveyor.direction * push;
+        }
+    }
+}
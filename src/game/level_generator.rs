@@ -0,0 +1,494 @@
+use std::str::FromStr;
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_ecs_ldtk::{
+    ldtk::{LayerInstance, Level},
+    prelude::FieldValue,
+    EntityInstance, LdtkAsset, LdtkLevel,
+};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{app_state::loading::LevelAssets, entity::spawner::EnemyType};
+
+use super::{
+    level_manager::{level_setup, LevelManager},
+    GameState,
+};
+
+// Generated levels are named `Procedural_0`, `Procedural_1`, ...
+pub const PROCEDURAL_LEVEL_PREFIX: &str = "Procedural_";
+
+// LDtk levels whose identifier starts with this, or that have a `template` bool field set, are used
+// as rooms. If the project has none, every authored level without a boss is used as a room instead.
+const TEMPLATE_LEVEL_PREFIX: &str = "Template";
+
+// Size of a room cell in pixels, matches the IntGrid and Tiles layers
+const CELL_SIZE: i32 = 64;
+
+// IntGrid value registered as `NonPassable`
+const SOLID_INT_GRID_VALUE: i32 = 1;
+
+// How many cells rooms can be shifted up or down relative to each other
+const ROOM_VERTICAL_JITTER: i32 = 4;
+
+pub struct LevelGeneratorPlugin;
+
+impl Plugin for LevelGeneratorPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<LevelGenerator>().add_system(
+            generate_procedural_level
+                .in_schedule(OnEnter(GameState::SetupLevel))
+                .before(level_setup),
+        );
+    }
+}
+
+#[derive(Resource, Reflect)]
+pub struct LevelGenerator {
+    pub level_count: usize,
+    pub rooms_per_level: usize,
+    pub corridor_length: i32,
+    pub base_budget: u32,
+    pub budget_per_level: u32,
+}
+
+impl Default for LevelGenerator {
+    fn default() -> Self {
+        Self {
+            level_count: 9,
+            rooms_per_level: 3,
+            corridor_length: 3,
+            base_budget: 12,
+            budget_per_level: 6,
+        }
+    }
+}
+
+impl LevelGenerator {
+    // Total difficulty to spend on spawners for the given level of the run
+    pub fn budget(&self, levels_cleared: usize) -> u32 {
+        self.base_budget + self.budget_per_level * levels_cleared as u32
+    }
+}
+
+pub fn procedural_level_identifier(index: usize) -> String {
+    format!("{}{}", PROCEDURAL_LEVEL_PREFIX, index)
+}
+
+pub fn is_procedural_level(identifier: &str) -> bool {
+    identifier.starts_with(PROCEDURAL_LEVEL_PREFIX)
+}
+
+// How much of the difficulty budget a single enemy of this type costs
+fn enemy_cost(enemy_type: &EnemyType) -> u32 {
+    match enemy_type {
+        EnemyType::Slimer => 1,
+        EnemyType::Skuller => 1,
+        EnemyType::Goblin => 2,
+        EnemyType::Mutant => 3,
+        EnemyType::Adept => 5,
+        EnemyType::GoblinBrute => 6,
+        // Bosses are never placed by the generator
+        EnemyType::LabBoss | EnemyType::Sorcerian => u32::MAX,
+    }
+}
+
+fn is_template(level: &Level) -> bool {
+    level.identifier.starts_with(TEMPLATE_LEVEL_PREFIX)
+        || level.field_instances.iter().any(|field_instance| {
+            field_instance.identifier == "template"
+                && matches!(field_instance.value, FieldValue::Bool(true))
+        })
+}
+
+// Boss levels make poor rooms, their encounter would be copied into the middle of a generated level
+fn is_boss_level(level: &Level) -> bool {
+    level
+        .layer_instances
+        .iter()
+        .flatten()
+        .flat_map(|layer| layer.entity_instances.iter())
+        .filter(|entity_instance| entity_instance.identifier == "Spawner")
+        .flat_map(|entity_instance| entity_instance.field_instances.iter())
+        .any(|field_instance| match &field_instance.value {
+            FieldValue::Enum(Some(enemy_type)) if field_instance.identifier == "enemy_type" => {
+                EnemyType::from_str(enemy_type).map_or(false, |enemy_type| enemy_type.is_boss())
+            }
+            _ => false,
+        })
+}
+
+// Builds the level the `LevelManager` is about to enter if it's a procedural one, and swaps it into
+// the LDtk asset so `level_setup` can spawn it like any other level
+pub fn generate_procedural_level(
+    level_manager: Res<LevelManager>,
+    level_generator: Res<LevelGenerator>,
+    level_assets: Res<LevelAssets>,
+    mut ldtk_assets: ResMut<Assets<LdtkAsset>>,
+    mut ldtk_levels: ResMut<Assets<LdtkLevel>>,
+) {
+    let identifier = match &level_manager.next_level {
+        Some(identifier) if is_procedural_level(identifier) => identifier.clone(),
+        _ => return,
+    };
+    let ldtk_asset = match ldtk_assets.get_mut(&level_assets.ldtk) {
+        Some(ldtk_asset) => ldtk_asset,
+        None => return,
+    };
+
+    let mut rng = rand::thread_rng();
+    let budget = level_generator.budget(level_manager.levels_cleared());
+    let level = match assemble_level(
+        &ldtk_asset.project.levels,
+        &identifier,
+        &level_generator,
+        budget,
+        &mut rng,
+    ) {
+        Some(level) => level,
+        None => return,
+    };
+
+    // Only the level currently being played needs to exist
+    let old_levels: Vec<String> = ldtk_asset
+        .project
+        .levels
+        .iter()
+        .filter(|level| is_procedural_level(&level.identifier))
+        .map(|level| level.iid.clone())
+        .collect();
+    ldtk_asset
+        .project
+        .levels
+        .retain(|level| !is_procedural_level(&level.identifier));
+    for iid in old_levels {
+        if let Some(handle) = ldtk_asset.level_map.remove(&iid) {
+            ldtk_levels.remove(handle);
+        }
+    }
+
+    let handle = ldtk_levels.add(LdtkLevel {
+        level: level.clone(),
+        background_image: None,
+    });
+    ldtk_asset.level_map.insert(level.iid.clone(), handle);
+    ldtk_asset.project.levels.push(level);
+}
+
+fn assemble_level(
+    levels: &[Level],
+    identifier: &str,
+    level_generator: &LevelGenerator,
+    budget: u32,
+    rng: &mut impl Rng,
+) -> Option<Level> {
+    let mut templates: Vec<&Level> = levels.iter().filter(|level| is_template(level)).collect();
+    if templates.is_empty() {
+        templates = levels
+            .iter()
+            .filter(|level| !is_procedural_level(&level.identifier) && !is_boss_level(level))
+            .collect();
+    }
+    let base = *templates.first()?;
+
+    let rooms: Vec<&Level> = (0..level_generator.rooms_per_level.max(1))
+        .filter_map(|_| templates.choose(rng).copied())
+        .collect();
+    let room_sizes: Vec<IVec2> = rooms
+        .iter()
+        .map(|room| IVec2::new(room.px_wid / CELL_SIZE, room.px_hei / CELL_SIZE))
+        .collect();
+
+    // Lay the rooms out left to right, each shifted up or down a little
+    let corridor_length = level_generator.corridor_length.max(1);
+    let level_height = room_sizes.iter().map(|size| size.y).max()? + ROOM_VERTICAL_JITTER;
+    let mut room_offsets = Vec::new();
+    let mut x = 0;
+    for size in room_sizes.iter() {
+        room_offsets.push(IVec2::new(x, rng.gen_range(0..=level_height - size.y)));
+        x += size.x + corridor_length;
+    }
+    let level_width = x - corridor_length;
+    let corridor_cells = corridor_cells(&room_offsets, &room_sizes, corridor_length);
+
+    let mut level = base.clone();
+    level.identifier = identifier.to_string();
+    level.iid = format!("{}-{}", identifier, rng.gen::<u32>());
+    level.uid = levels.iter().map(|level| level.uid).max().unwrap_or(0) + 1;
+    level.px_wid = level_width * CELL_SIZE;
+    level.px_hei = level_height * CELL_SIZE;
+    level.field_instances.clear();
+
+    let (px_wid, px_hei) = (level.px_wid, level.px_hei);
+    let layers = level.layer_instances.as_mut()?;
+    for layer in layers.iter_mut() {
+        let room_layers: Vec<Option<&LayerInstance>> = rooms
+            .iter()
+            .map(|room| {
+                room.layer_instances
+                    .iter()
+                    .flatten()
+                    .find(|room_layer| room_layer.identifier == layer.identifier)
+            })
+            .collect();
+        assemble_layer(
+            layer,
+            &room_layers,
+            &room_offsets,
+            &corridor_cells,
+            IVec2::new(px_wid, px_hei),
+        );
+    }
+
+    place_spawners(layers, levels, &room_offsets, &room_sizes, budget, rng);
+
+    Some(level)
+}
+
+// Cells carved out between each pair of neighbouring rooms, going from the center of one room to the
+// center of the next with a single bend in the gap between them
+fn corridor_cells(
+    room_offsets: &[IVec2],
+    room_sizes: &[IVec2],
+    corridor_length: i32,
+) -> Vec<IVec2> {
+    let mut cells = Vec::new();
+    for i in 1..room_offsets.len() {
+        let from = room_offsets[i - 1] + room_sizes[i - 1] / 2;
+        let to = room_offsets[i] + room_sizes[i] / 2;
+        let bend_x = room_offsets[i - 1].x + room_sizes[i - 1].x + corridor_length / 2;
+
+        for x in from.x..=bend_x {
+            cells.push(IVec2::new(x, from.y));
+            cells.push(IVec2::new(x, from.y + 1));
+        }
+        for y in from.y.min(to.y)..=from.y.max(to.y) + 1 {
+            cells.push(IVec2::new(bend_x, y));
+            cells.push(IVec2::new(bend_x + 1, y));
+        }
+        for x in bend_x..=to.x {
+            cells.push(IVec2::new(x, to.y));
+            cells.push(IVec2::new(x, to.y + 1));
+        }
+    }
+    cells
+}
+
+fn assemble_layer(
+    layer: &mut LayerInstance,
+    room_layers: &[Option<&LayerInstance>],
+    room_offsets: &[IVec2],
+    corridor_cells: &[IVec2],
+    level_size: IVec2,
+) {
+    let grid_size = layer.grid_size;
+    let cells_per_room_cell = (CELL_SIZE / grid_size).max(1);
+    let is_int_grid = !layer.int_grid_csv.is_empty();
+    let has_tiles = !layer.grid_tiles.is_empty();
+    let floor_tile = layer.grid_tiles.get(layer.grid_tiles.len() / 2).cloned();
+
+    layer.c_wid = level_size.x / grid_size;
+    layer.c_hei = level_size.y / grid_size;
+    let c_wid = layer.c_wid;
+    layer.int_grid_csv = if is_int_grid {
+        // Everything that isn't a room or corridor is wall
+        vec![SOLID_INT_GRID_VALUE; (layer.c_wid * layer.c_hei) as usize]
+    } else {
+        Vec::new()
+    };
+    layer.grid_tiles.clear();
+    layer.auto_layer_tiles.clear();
+    layer.entity_instances.clear();
+
+    let last_room = room_layers.len().saturating_sub(1);
+    for (room_index, (room_layer, room_offset)) in
+        room_layers.iter().zip(room_offsets.iter()).enumerate()
+    {
+        let room_layer = match room_layer {
+            Some(room_layer) => room_layer,
+            None => continue,
+        };
+        let offset_px = *room_offset * CELL_SIZE;
+        let offset_cells = offset_px / grid_size;
+
+        if is_int_grid {
+            for (i, value) in room_layer.int_grid_csv.iter().enumerate() {
+                let cell = IVec2::new(i as i32 % room_layer.c_wid, i as i32 / room_layer.c_wid)
+                    + offset_cells;
+                layer.int_grid_csv[(cell.y * c_wid + cell.x) as usize] = *value;
+            }
+        }
+
+        for tile in room_layer.grid_tiles.iter() {
+            let mut tile = tile.clone();
+            tile.px += offset_px;
+            let cell = tile.px / grid_size;
+            tile.d = vec![cell.y * c_wid + cell.x];
+            layer.grid_tiles.push(tile);
+        }
+        for tile in room_layer.auto_layer_tiles.iter() {
+            let mut tile = tile.clone();
+            tile.px += offset_px;
+            layer.auto_layer_tiles.push(tile);
+        }
+
+        // Spawners are placed from the difficulty budget, and only the first room gets the start
+        // and the last room the exit
+        for entity_instance in room_layer.entity_instances.iter() {
+            let keep = match entity_instance.identifier.as_str() {
+                "Spawner" => false,
+                "LevelStart" => room_index == 0,
+                "LevelExit" => room_index == last_room,
+                _ => true,
+            };
+            if !keep {
+                continue;
+            }
+            let mut entity_instance = entity_instance.clone();
+            entity_instance.px += offset_px;
+            entity_instance.grid = entity_instance.px / grid_size;
+            entity_instance.iid = format!("{}-{}", entity_instance.iid, room_index);
+            layer.entity_instances.push(entity_instance);
+        }
+    }
+
+    if is_int_grid {
+        for cell in corridor_cells {
+            for dy in 0..cells_per_room_cell {
+                for dx in 0..cells_per_room_cell {
+                    let x = cell.x * cells_per_room_cell + dx;
+                    let y = cell.y * cells_per_room_cell + dy;
+                    if x >= 0 && x < layer.c_wid && y >= 0 && y < layer.c_hei {
+                        layer.int_grid_csv[(y * c_wid + x) as usize] = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    // Corridors outside of rooms have no floor, so reuse one of the room's floor tiles for them
+    if let (true, Some(floor_tile)) = (has_tiles, floor_tile) {
+        let covered: HashSet<IVec2> = layer.grid_tiles.iter().map(|tile| tile.px).collect();
+        for cell in corridor_cells {
+            let px = *cell * CELL_SIZE;
+            if covered.contains(&px) {
+                continue;
+            }
+            let mut tile = floor_tile.clone();
+            tile.px = px;
+            let cell = px / grid_size;
+            tile.d = vec![cell.y * c_wid + cell.x];
+            layer.grid_tiles.push(tile);
+        }
+    }
+}
+
+// Spend the difficulty budget on `Spawner` entities scattered over the floor of every room but the
+// first, so the player isn't ambushed at the start
+fn place_spawners(
+    layers: &mut [LayerInstance],
+    levels: &[Level],
+    room_offsets: &[IVec2],
+    room_sizes: &[IVec2],
+    budget: u32,
+    rng: &mut impl Rng,
+) {
+    // The authored spawners are used as a prototype so the field definitions stay intact
+    let prototype = levels
+        .iter()
+        .flat_map(|level| level.layer_instances.iter().flatten())
+        .find_map(|layer| {
+            layer
+                .entity_instances
+                .iter()
+                .find(|entity_instance| entity_instance.identifier == "Spawner")
+                .map(|entity_instance| (layer.identifier.clone(), entity_instance.clone()))
+        });
+    let (entity_layer_identifier, prototype) = match prototype {
+        Some(prototype) => prototype,
+        None => return,
+    };
+
+    let floor_cells: Vec<IVec2> = {
+        let int_grid = layers.iter().find(|layer| !layer.int_grid_csv.is_empty());
+        let first_room = if room_offsets.len() > 1 { 1 } else { 0 };
+        room_offsets
+            .iter()
+            .zip(room_sizes.iter())
+            .skip(first_room)
+            .flat_map(|(offset, size)| {
+                (1..size.y - 1)
+                    .flat_map(move |y| (1..size.x - 1).map(move |x| *offset + IVec2::new(x, y)))
+            })
+            .filter(|cell| match int_grid {
+                Some(layer) => {
+                    let scale = (CELL_SIZE / layer.grid_size).max(1);
+                    let index = (cell.y * scale * layer.c_wid + cell.x * scale) as usize;
+                    layer.int_grid_csv.get(index) == Some(&0)
+                }
+                None => true,
+            })
+            .collect()
+    };
+
+    let entity_layer = match layers
+        .iter_mut()
+        .find(|layer| layer.identifier == entity_layer_identifier)
+    {
+        Some(entity_layer) => entity_layer,
+        None => return,
+    };
+
+    let enemy_types = [
+        EnemyType::Slimer,
+        EnemyType::Skuller,
+        EnemyType::Goblin,
+        EnemyType::Mutant,
+        EnemyType::Adept,
+        EnemyType::GoblinBrute,
+    ];
+
+    let mut remaining = budget;
+    let mut spawner_index = 0;
+    while remaining > 0 {
+        let affordable: Vec<&EnemyType> = enemy_types
+            .iter()
+            .filter(|enemy_type| enemy_cost(enemy_type) <= remaining)
+            .collect();
+        let (enemy_type, cell) = match (affordable.choose(rng), floor_cells.choose(rng)) {
+            (Some(enemy_type), Some(cell)) => (*enemy_type, *cell),
+            _ => break,
+        };
+        let cost = enemy_cost(enemy_type);
+        let spawn_count = rng.gen_range(1..=(remaining / cost).min(6));
+        remaining -= spawn_count * cost;
+
+        let mut spawner = prototype.clone();
+        spawner.px =
+            cell * CELL_SIZE + IVec2::splat(CELL_SIZE / 2) - IVec2::splat(spawner.width / 2);
+        spawner.grid = spawner.px / entity_layer.grid_size;
+        spawner.iid = format!("{}-spawner-{}", prototype.iid, spawner_index);
+        set_spawner_fields(&mut spawner, enemy_type, spawn_count, rng);
+        entity_layer.entity_instances.push(spawner);
+        spawner_index += 1;
+    }
+}
+
+fn set_spawner_fields(
+    spawner: &mut EntityInstance,
+    enemy_type: &EnemyType,
+    spawn_count: u32,
+    rng: &mut impl Rng,
+) {
+    let spawn_rate = rng.gen_range(1..=spawn_count.min(3));
+    let timer = rng.gen_range(1.0..4.0);
+    for field_instance in spawner.field_instances.iter_mut() {
+        field_instance.value = match field_instance.identifier.as_str() {
+            "timer" => FieldValue::Float(Some(timer)),
+            "spawn_rate" => FieldValue::Int(Some(spawn_rate as i32)),
+            "spawn_count" => FieldValue::Int(Some(spawn_count as i32)),
+            "enemy_type" => FieldValue::Enum(Some(enemy_type.identifier().to_string())),
+            _ => continue,
+        };
+    }
+}
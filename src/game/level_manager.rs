@@ -3,7 +3,7 @@ use std::str::FromStr;
 use bevy::prelude::*;
 use bevy_ecs_ldtk::{
     prelude::{FieldValue, LdtkEntityAppExt, LdtkIntCellAppExt},
-    EntityInstance, IntGridCell, LdtkEntity, LdtkIntCell, LdtkSettings, LdtkWorldBundle,
+    EntityInstance, IntGridCell, LdtkAsset, LdtkEntity, LdtkIntCell, LdtkSettings, LdtkWorldBundle,
     LevelSelection,
 };
 use bevy_kira_audio::AudioChannel;
@@ -18,13 +18,24 @@ use crate::{
     },
     camera::{camera_clamp_to_current_level, camera_movement_system},
     entity::{
+        faction::Faction,
+        level_exit::LevelExit,
         spawner::{spawn_system, EnemyType, Spawner},
         Enemy,
     },
     PIXELS_PER_METER,
 };
 
-use super::GameState;
+use super::{
+    arena::ARENA_LEVEL,
+    breakable::{BreakablePropEntityBundle, BreakableWall, SecretAreaEntityBundle},
+    hazard::{
+        AcidPoolEntityBundle, ConveyorEntityBundle, ExplosiveBarrelEntityBundle,
+        SpikeFloorEntityBundle,
+    },
+    level_generator::{is_procedural_level, procedural_level_identifier, LevelGenerator},
+    GameMode, GameState,
+};
 
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct NonPassable {
@@ -36,16 +47,25 @@ pub struct NonPassable {
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct WallColliderBundle {
     pub collider: Collider,
+    pub wall: Wall,
 }
 
+// Marker component for wall colliders, e.g. to check what blocks line of sight
+#[derive(Component, Clone, Debug, Default)]
+pub struct Wall;
+
 impl From<IntGridCell> for WallColliderBundle {
     fn from(_int_grid_cell: IntGridCell) -> WallColliderBundle {
         WallColliderBundle {
             collider: Collider::cuboid(PIXELS_PER_METER * 1.0, PIXELS_PER_METER * 1.0),
+            wall: Wall,
         }
     }
 }
 
+// Elite chance of LDtk spawners that don't set one, before difficulty scaling
+const DEFAULT_ELITE_CHANCE: f32 = 0.05;
+
 #[derive(Clone, Default, Bundle, LdtkEntity)]
 pub struct SpawnerEntityBundle {
     #[from_entity_instance]
@@ -70,6 +90,14 @@ fn find_field_value<'a>(
         .map(|field_instance| &field_instance.value)
 }
 
+// Half the size an entity was given in LDtk, for ones sized in the editor like hazards and seals
+pub fn entity_half_extents(entity_instance: &EntityInstance) -> Vec2 {
+    Vec2::new(
+        entity_instance.width as f32 / 2.0,
+        entity_instance.height as f32 / 2.0,
+    )
+}
+
 impl From<&EntityInstance> for SpawnerBundle {
     fn from(entity_instance: &EntityInstance) -> SpawnerBundle {
         // Helper closure to get field value and simplify error handling
@@ -104,17 +132,25 @@ impl From<&EntityInstance> for SpawnerBundle {
                         spawn_rate: 1,
                         spawn_count: 10,
                         enemy_type: EnemyType::Slimer,
+                        elite_chance: DEFAULT_ELITE_CHANCE,
                     },
                 };
             }
         };
 
+        // Optional, older spawners don't have the field
+        let elite_chance = match find_field_value(entity_instance, "elite_chance") {
+            Some(FieldValue::Float(Some(elite_chance))) => *elite_chance,
+            _ => DEFAULT_ELITE_CHANCE,
+        };
+
         SpawnerBundle {
             spawner: Spawner {
                 timer,
                 spawn_rate,
                 spawn_count,
                 enemy_type: EnemyType::from_str(enemy_type_string).unwrap(),
+                elite_chance,
             },
         }
     }
@@ -129,7 +165,14 @@ impl Plugin for LevelManagerPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.insert_resource(LdtkSettings { ..default() })
             .register_ldtk_int_cell::<NonPassable>(1)
-            .register_ldtk_entity::<SpawnerEntityBundle>("Spawner");
+            .register_ldtk_int_cell::<BreakableWall>(2)
+            .register_ldtk_entity::<SpawnerEntityBundle>("Spawner")
+            .register_ldtk_entity::<SpikeFloorEntityBundle>("SpikeFloor")
+            .register_ldtk_entity::<AcidPoolEntityBundle>("AcidPool")
+            .register_ldtk_entity::<ExplosiveBarrelEntityBundle>("ExplosiveBarrel")
+            .register_ldtk_entity::<ConveyorEntityBundle>("Conveyor")
+            .register_ldtk_entity::<BreakablePropEntityBundle>("Breakable")
+            .register_ldtk_entity::<SecretAreaEntityBundle>("SecretArea");
 
         app.add_system(level_manager_setup.in_schedule(OnEnter(GameState::SetupLevelManager)))
             .add_system(level_manager_cleanup.in_schedule(OnExit(AppState::InGame)))
@@ -156,15 +199,26 @@ impl Plugin for LevelManagerPlugin {
 
 pub fn level_enemies_remaining_check(
     mut next_state: ResMut<NextState<GameState>>,
-    spawner_query: Query<&Spawner>,
-    enemy_query: Query<Entity, With<Enemy>>,
+    spawner_query: Query<(&Spawner, Option<&Faction>)>,
+    enemy_query: Query<Option<&Faction>, With<Enemy>>,
+    game_mode: Res<GameMode>,
 ) {
-    // Calculate the total number of enemies remaining
+    // The arena never runs out of enemies
+    if *game_mode == GameMode::Arena {
+        return;
+    }
+
+    // Calculate the total number of enemies remaining, friendly ones don't count
+    let hostile = |faction: Option<&Faction>| faction != Some(&Faction::Player);
     let remaining_spawns: u32 = spawner_query
         .iter()
-        .map(|spawner| spawner.spawn_count as u32)
+        .filter(|(_, faction)| hostile(*faction))
+        .map(|(spawner, _)| spawner.spawn_count as u32)
         .sum();
-    let spawned_enemies: u32 = enemy_query.iter().count() as u32;
+    let spawned_enemies: u32 = enemy_query
+        .iter()
+        .filter(|faction| hostile(*faction))
+        .count() as u32;
     let total_enemies = remaining_spawns + spawned_enemies;
 
     // Check if all enemies are killed and the level is complete
@@ -173,13 +227,98 @@ pub fn level_enemies_remaining_check(
     }
 }
 
+// Exits with this destination end the run instead of leading to another level
+pub const ENDGAME_DESTINATION: &str = "Ending";
+
+// Levels form a graph: each level is an LDtk level identifier, and the edges are the `destination`
+// fields of the `LevelExit`s placed in it
 #[derive(Resource, Reflect, Default)]
 pub struct LevelManager {
-    pub current_level: usize,
+    pub current_level: Option<String>,
+    pub next_level: Option<String>,
+    pub history: Vec<String>,
+    pub bonus_offers: usize,
+    // How many generated levels make up the run, 0 for the authored campaign
+    pub procedural_levels: usize,
+}
+
+impl LevelManager {
+    // Number of levels cleared so far this run
+    pub fn levels_cleared(&self) -> usize {
+        self.history.len()
+    }
+
+    // Where the given exit leads from the current level, or None if it ends the run
+    pub fn resolve_destination(
+        &self,
+        level_exit: &LevelExit,
+        level_identifiers: &[String],
+    ) -> Option<String> {
+        match &level_exit.destination {
+            Some(destination) if destination == ENDGAME_DESTINATION => None,
+            Some(destination) => Some(destination.clone()),
+            // Procedural runs just chain generated levels together
+            None if self.procedural_levels > 0 => {
+                let next_index = self.levels_cleared() + 1;
+                (next_index < self.procedural_levels)
+                    .then(|| procedural_level_identifier(next_index))
+            }
+            // No destination, fall back to the next level in the project
+            None => {
+                let current_index = self.current_level.as_ref().and_then(|current_level| {
+                    level_identifiers
+                        .iter()
+                        .position(|identifier| identifier == current_level)
+                })?;
+                level_identifiers.get(current_index + 1).cloned()
+            }
+        }
+    }
 }
 
-pub fn level_manager_setup(mut commands: Commands, mut next_state: ResMut<NextState<GameState>>) {
-    commands.insert_resource(LevelManager { current_level: 0 });
+pub fn level_identifiers(ldtk_asset: &LdtkAsset) -> Vec<String> {
+    ldtk_asset
+        .project
+        .levels
+        .iter()
+        .map(|level| level.identifier.clone())
+        .collect()
+}
+
+pub fn level_manager_setup(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    level_assets: Res<LevelAssets>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    game_mode: Res<GameMode>,
+    level_generator: Res<LevelGenerator>,
+) {
+    let level_manager = match *game_mode {
+        GameMode::Campaign => LevelManager {
+            next_level: ldtk_assets
+                .get(&level_assets.ldtk)
+                .and_then(|ldtk_asset| level_identifiers(ldtk_asset).into_iter().next()),
+            ..default()
+        },
+        GameMode::Procedural => LevelManager {
+            next_level: Some(procedural_level_identifier(0)),
+            procedural_levels: level_generator.level_count,
+            ..default()
+        },
+        GameMode::Arena => LevelManager {
+            next_level: ldtk_assets.get(&level_assets.ldtk).and_then(|ldtk_asset| {
+                let level_identifiers = level_identifiers(ldtk_asset);
+                level_identifiers
+                    .iter()
+                    .find(|identifier| *identifier == ARENA_LEVEL)
+                    .or_else(|| level_identifiers.first())
+                    .cloned()
+            }),
+            ..default()
+        },
+    };
+
+    commands.insert_resource(level_manager);
     next_state.set(GameState::SetupLevel);
 }
 
@@ -192,38 +331,70 @@ pub fn level_setup(
     _state: ResMut<State<GameState>>,
     mut level_manager: ResMut<LevelManager>,
     level_assets: Res<LevelAssets>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
     mut next_state: ResMut<NextState<GameState>>,
     background: Res<AudioChannel<Background>>,
     music_assets: Res<MusicAssets>,
 ) {
-    if level_manager.current_level == 0 || level_manager.current_level == 1 {
+    let level_identifiers = ldtk_assets
+        .get(&level_assets.ldtk)
+        .map(level_identifiers)
+        .unwrap_or_default();
+
+    // Move along the graph to the level chosen at the last exit
+    let level = match level_manager
+        .next_level
+        .take()
+        .or_else(|| level_identifiers.first().cloned())
+    {
+        Some(level) => level,
+        None => return,
+    };
+    if let Some(previous_level) = level_manager.current_level.replace(level.clone()) {
+        level_manager.history.push(previous_level);
+    }
+    level_manager.bonus_offers = 0;
+
+    // Music follows the level's position in the project, or in the run for generated levels
+    let level_index = if is_procedural_level(&level) {
+        level_manager.levels_cleared()
+    } else {
+        level_identifiers
+            .iter()
+            .position(|identifier| *identifier == level)
+            .unwrap_or(0)
+    };
+    if level_index == 0 || level_index == 1 {
         background.stop();
         background.play(music_assets.labs.clone()).looped();
     }
-    if level_manager.current_level == 2 {
+    if level_index == 2 {
         background.stop();
         background.play(music_assets.labsboss.clone()).looped();
     }
-    if level_manager.current_level == 3 || level_manager.current_level == 4 {
+    if level_index == 3 || level_index == 4 {
         background.stop();
         background.play(music_assets.tower.clone()).looped();
     }
-    if level_manager.current_level == 5 {
+    if level_index == 5 {
         background.stop();
         background.play(music_assets.towerboss.clone()).looped();
     }
-    if level_manager.current_level == 6 || level_manager.current_level == 7 {
+    if level_index == 6 || level_index == 7 {
         background.stop();
         background.play(music_assets.crystal.clone()).looped();
     }
-    if level_manager.current_level == 8 {
+    if level_index == 8 {
         background.stop();
         background.play(music_assets.sorcerianboss.clone()).looped();
     }
+    // The arena comes after the campaign in the project, it gets the crystal caves' track
+    if level == ARENA_LEVEL {
+        background.stop();
+        background.play(music_assets.crystal.clone()).looped();
+    }
 
-    // Increment the current_level
-    commands.insert_resource(LevelSelection::Index(level_manager.current_level));
-    level_manager.current_level += 1;
+    commands.insert_resource(LevelSelection::Identifier(level));
 
     // Spawn the level
     commands
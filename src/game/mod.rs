@@ -4,20 +4,39 @@ use crate::{
     animation::SpriteSheetAnimationPlugin,
     behaviour::BehaviourPlugin,
     camera::CameraPlugin,
-    entity::{creature::CreaturePlugin, player::PlayerPlugin, spawner::SpawnerPlugin, EnemyPlugin},
+    entity::{
+        ability::AbilityPlugin, creature::CreaturePlugin, elite::ElitePlugin,
+        faction::FactionPlugin, player::PlayerPlugin, projectile::ProjectilePlugin,
+        spawner::SpawnerPlugin, telegraph::TelegraphPlugin, EnemyPlugin,
+    },
 };
 
 use self::{
-    endgame_cutscene::EndgameCutscenePlugin, level_manager::LevelManagerPlugin,
-    mutation_manager::MutationManagerPlugin, mutation_selection::MutationSelectionPlugin,
-    opening_cutscene::OpeningCutscenePlugin, ui::UiPlugin,
+    arena::ArenaPlugin, boss_encounter::BossEncounterPlugin, breakable::BreakablePlugin,
+    difficulty::DifficultyPlugin, endgame_cutscene::EndgameCutscenePlugin,
+    game_over::GameOverPlugin, hazard::HazardPlugin, level_generator::LevelGeneratorPlugin,
+    level_manager::LevelManagerPlugin, mutation_manager::MutationManagerPlugin,
+    mutation_selection::MutationSelectionPlugin, opening_cutscene::OpeningCutscenePlugin,
+    pickup::PickupPlugin, run_stats::RunStatsPlugin, shop::ShopPlugin, synergy::SynergyPlugin,
+    ui::UiPlugin,
 };
 
+pub mod arena;
+pub mod boss_encounter;
+pub mod breakable;
+pub mod difficulty;
 pub mod endgame_cutscene;
+pub mod game_over;
+pub mod hazard;
+pub mod level_generator;
 pub mod level_manager;
 pub mod mutation_manager;
 pub mod mutation_selection;
 pub mod opening_cutscene;
+pub mod pickup;
+pub mod run_stats;
+pub mod shop;
+pub mod synergy;
 pub mod ui;
 
 pub struct GamePlugin;
@@ -25,18 +44,35 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_state::<GameState>()
+            .init_resource::<GameMode>()
             .add_plugin(OpeningCutscenePlugin)
             .add_plugin(EndgameCutscenePlugin)
             .add_plugin(CameraPlugin)
             .add_plugin(SpriteSheetAnimationPlugin)
             .add_plugin(PlayerPlugin)
+            .add_plugin(AbilityPlugin)
             .add_plugin(CreaturePlugin)
+            .add_plugin(FactionPlugin)
             .add_plugin(EnemyPlugin)
             .add_plugin(BehaviourPlugin)
             .add_plugin(SpawnerPlugin)
+            .add_plugin(ProjectilePlugin)
+            .add_plugin(TelegraphPlugin)
+            .add_plugin(ElitePlugin)
             .add_plugin(LevelManagerPlugin)
+            .add_plugin(LevelGeneratorPlugin)
+            .add_plugin(ArenaPlugin)
+            .add_plugin(BossEncounterPlugin)
+            .add_plugin(HazardPlugin)
+            .add_plugin(BreakablePlugin)
+            .add_plugin(PickupPlugin)
+            .add_plugin(GameOverPlugin)
+            .add_plugin(RunStatsPlugin)
+            .add_plugin(DifficultyPlugin)
             .add_plugin(MutationManagerPlugin)
             .add_plugin(MutationSelectionPlugin)
+            .add_plugin(ShopPlugin)
+            .add_plugin(SynergyPlugin)
             .add_plugin(UiPlugin);
     }
 }
@@ -52,4 +88,14 @@ pub enum GameState {
     LevelComplete,
     MutationSelection,
     EndgameCutscene,
+    GameOver,
+}
+
+// The kind of run picked from the main menu
+#[derive(Resource, Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum GameMode {
+    #[default]
+    Campaign,
+    Procedural,
+    Arena,
 }
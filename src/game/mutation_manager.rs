@@ -1,6 +1,11 @@
 use bevy::prelude::*;
 
-use crate::app_state::AppState;
+use rand::Rng;
+
+use crate::{app_state::AppState, entity::creature::EnemyKilled};
+
+// Screens in a row without a rare offer before one is guaranteed
+pub const PITY_THRESHOLD: u32 = 3;
 
 pub struct MutationManagerPlugin;
 
@@ -8,43 +13,75 @@ impl Plugin for MutationManagerPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_system(mutation_manager_setup.in_schedule(OnEnter(AppState::InGame)))
             .insert_resource(MutationManager::new())
-            .add_system(mutation_manager_cleanup.in_schedule(OnExit(AppState::InGame)));
+            .add_system(mutation_manager_cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(boss_rare_offer_system.in_set(OnUpdate(AppState::InGame)));
     }
 }
 
 #[derive(Debug, PartialEq, Clone, Resource)]
 pub struct MutationManager {
     pub all_mutations: Vec<Mutation>,
+    pub all_synergies: Vec<Synergy>,
     pub player_mutations: Vec<Mutation>,
     pub player_mutations_version: u32,
+    // Never offered again this run
+    pub banned: Vec<MutationType>,
+    pub screens_since_rare: u32,
+    // Set by beating a boss, the next screen leads with a rare
+    pub rare_guaranteed: bool,
 }
 
 impl MutationManager {
     fn new() -> Self {
         let all_mutations = vec![
-            Mutation::new(MutationType::Hemophilia, 0),
-            Mutation::new(MutationType::HeavyBones, 1),
-            Mutation::new(MutationType::DrySkin, 2),
-            Mutation::new(MutationType::Rage, 3),
-            Mutation::new(MutationType::BrittleBones, 4),
-            Mutation::new(MutationType::Vampirism, 5),
-            Mutation::new(MutationType::Dizziness, 6),
-            Mutation::new(MutationType::Cyclone, 7),
-            Mutation::new(MutationType::Repulsion, 8),
-            Mutation::new(MutationType::PoisonBlood, 9),
-            Mutation::new(MutationType::Drowziness, 10),
-            Mutation::new(MutationType::Lasers, 11),
-            Mutation::new(MutationType::Shrink, 12),
-            Mutation::new(MutationType::Grow, 13),
-            Mutation::new(MutationType::Reflect, 14),
-            Mutation::new(MutationType::RubberBody, 15),
-            Mutation::new(MutationType::BowlingBall, 16),
+            Mutation::new(MutationType::Hemophilia, 0, Rarity::Uncommon, 3),
+            Mutation::new(MutationType::HeavyBones, 1, Rarity::Uncommon, 3),
+            Mutation::new(MutationType::DrySkin, 2, Rarity::Common, 3),
+            Mutation::new(MutationType::Rage, 3, Rarity::Uncommon, 3),
+            Mutation::new(MutationType::BrittleBones, 4, Rarity::Common, 1),
+            Mutation::new(MutationType::Vampirism, 5, Rarity::Rare, 3),
+            Mutation::new(MutationType::Dizziness, 6, Rarity::Common, 1),
+            Mutation::new(MutationType::Cyclone, 7, Rarity::Rare, 1),
+            Mutation::new(MutationType::Repulsion, 8, Rarity::Uncommon, 3),
+            Mutation::new(MutationType::PoisonBlood, 9, Rarity::Uncommon, 1),
+            Mutation::new(MutationType::Drowziness, 10, Rarity::Common, 1),
+            Mutation::new(MutationType::Lasers, 11, Rarity::Rare, 2),
+            Mutation::new(MutationType::Shrink, 12, Rarity::Common, 1),
+            Mutation::new(MutationType::Grow, 13, Rarity::Common, 1),
+            Mutation::new(MutationType::Reflect, 14, Rarity::Uncommon, 1),
+            Mutation::new(MutationType::RubberBody, 15, Rarity::Common, 1),
+            Mutation::new(MutationType::BowlingBall, 16, Rarity::Rare, 1),
+        ];
+
+        let all_synergies = vec![
+            Synergy::new(
+                SynergyType::Bloodbath,
+                "Bloodbath",
+                "Enemies that die bleeding heal you",
+                [MutationType::Hemophilia, MutationType::Vampirism],
+            ),
+            Synergy::new(
+                SynergyType::Pinball,
+                "Pinball",
+                "Rolls recharge twice as fast",
+                [MutationType::Cyclone, MutationType::BowlingBall],
+            ),
+            Synergy::new(
+                SynergyType::Berserker,
+                "Berserker",
+                "Getting hit is much more likely to enrage you",
+                [MutationType::Rage, MutationType::BrittleBones],
+            ),
         ];
 
         Self {
             all_mutations,
+            all_synergies,
             player_mutations: Vec::new(),
             player_mutations_version: 0,
+            banned: Vec::new(),
+            screens_since_rare: 0,
+            rare_guaranteed: false,
         }
     }
 
@@ -54,16 +91,32 @@ impl MutationManager {
             .any(|m| m.mutation_type == mutation_type)
     }
 
+    // 0 if the mutation isn't held
+    pub fn mutation_rank(&self, mutation_type: MutationType) -> u32 {
+        self.player_mutations
+            .iter()
+            .find(|m| m.mutation_type == mutation_type)
+            .map_or(0, |m| m.rank)
+    }
+
+    // Taking a mutation that's already held ranks it up, if it can go any higher
     pub fn add_mutation(&mut self, mutation_type: MutationType) {
-        if !self.has_mutation(mutation_type) {
-            if let Some(mutation) = self
-                .all_mutations
-                .iter()
-                .find(|m| m.mutation_type == mutation_type)
-            {
-                self.player_mutations.push(mutation.clone());
+        if let Some(mutation) = self
+            .player_mutations
+            .iter_mut()
+            .find(|m| m.mutation_type == mutation_type)
+        {
+            if mutation.rank < mutation.max_rank {
+                mutation.rank += 1;
                 self.player_mutations_version += 1;
             }
+        } else if let Some(mutation) = self
+            .all_mutations
+            .iter()
+            .find(|m| m.mutation_type == mutation_type)
+        {
+            self.player_mutations.push(mutation.clone());
+            self.player_mutations_version += 1;
         }
     }
 
@@ -73,6 +126,34 @@ impl MutationManager {
         self.player_mutations_version += 1;
     }
 
+    pub fn has_synergy(&self, synergy_type: SynergyType) -> bool {
+        self.active_synergies()
+            .any(|s| s.synergy_type == synergy_type)
+    }
+
+    // Synergies with both their mutations held
+    pub fn active_synergies(&self) -> impl Iterator<Item = &Synergy> {
+        self.all_synergies
+            .iter()
+            .filter(|s| s.mutations.iter().all(|m| self.has_mutation(*m)))
+    }
+
+    // Synergies that taking this mutation would unlock
+    pub fn synergies_completed_by(&self, mutation_type: MutationType) -> Vec<&Synergy> {
+        if self.has_mutation(mutation_type) {
+            return Vec::new();
+        }
+        self.all_synergies
+            .iter()
+            .filter(|s| s.mutations.contains(&mutation_type))
+            .filter(|s| {
+                s.mutations
+                    .iter()
+                    .all(|m| *m == mutation_type || self.has_mutation(*m))
+            })
+            .collect()
+    }
+
     pub fn unselected_mutations(&self) -> Vec<Mutation> {
         self.all_mutations
             .iter()
@@ -80,6 +161,73 @@ impl MutationManager {
             .cloned()
             .collect()
     }
+
+    // Held mutations that can still rank up, at the rank they'd go to
+    pub fn upgradable_mutations(&self) -> Vec<Mutation> {
+        self.player_mutations
+            .iter()
+            .filter(|m| m.rank < m.max_rank)
+            .map(|m| Mutation {
+                rank: m.rank + 1,
+                ..m.clone()
+            })
+            .collect()
+    }
+
+    pub fn ban_mutation(&mut self, mutation_type: MutationType) {
+        if !self.banned.contains(&mutation_type) {
+            self.banned.push(mutation_type);
+        }
+    }
+
+    pub fn offer_weight(&self, mutation: &Mutation) -> f32 {
+        let mut weight = mutation.rarity.weight();
+        // Rares get likelier with every screen that didn't offer one
+        if mutation.rarity == Rarity::Rare {
+            weight *= 1.0 + 0.5 * self.screens_since_rare as f32;
+        }
+        weight
+    }
+
+    // Everything that can still be offered, new mutations and upgrades alike, in weighted random
+    // order so callers can take however many they need from the front. A rare leads when one is
+    // guaranteed or the pity timer is up
+    pub fn roll_offers(&self, guarantee_rare: bool, rng: &mut impl Rng) -> Vec<Mutation> {
+        // Weighted shuffle, each key is u^(1/weight) and the biggest keys go first
+        let mut keyed = self
+            .unselected_mutations()
+            .into_iter()
+            .chain(self.upgradable_mutations())
+            .filter(|m| !self.banned.contains(&m.mutation_type))
+            .map(|m| {
+                let key = rng.gen_range(0.0f32..1.0).powf(1.0 / self.offer_weight(&m));
+                (key, m)
+            })
+            .collect::<Vec<_>>();
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut offers = keyed.into_iter().map(|(_, m)| m).collect::<Vec<_>>();
+
+        if guarantee_rare || self.screens_since_rare >= PITY_THRESHOLD {
+            if let Some(index) = offers.iter().position(|m| m.rarity == Rarity::Rare) {
+                let rare = offers.remove(index);
+                offers.insert(0, rare);
+            }
+        }
+        offers
+    }
+
+    // Call once per mutation screen with what ended up being offered
+    pub fn record_offers(&mut self, offered: &[MutationType]) {
+        let rare_offered = self
+            .all_mutations
+            .iter()
+            .any(|m| m.rarity == Rarity::Rare && offered.contains(&m.mutation_type));
+        if rare_offered {
+            self.screens_since_rare = 0;
+        } else {
+            self.screens_since_rare += 1;
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -103,17 +251,81 @@ pub enum MutationType {
     BowlingBall,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+}
+
+impl Rarity {
+    pub fn weight(&self) -> f32 {
+        match self {
+            Rarity::Common => 60.0,
+            Rarity::Uncommon => 30.0,
+            Rarity::Rare => 10.0,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Rarity::Common => Color::rgb(0.7, 0.7, 0.7),
+            Rarity::Uncommon => Color::rgb(0.3, 0.6, 1.0),
+            Rarity::Rare => Color::rgb(1.0, 0.5, 0.1),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Mutation {
     pub mutation_type: MutationType,
     pub icon_index: usize,
+    pub rarity: Rarity,
+    // Starts at 1 when first taken
+    pub rank: u32,
+    pub max_rank: u32,
 }
 
 impl Mutation {
-    fn new(mutation_type: MutationType, icon_index: usize) -> Self {
+    fn new(mutation_type: MutationType, icon_index: usize, rarity: Rarity, max_rank: u32) -> Self {
         Self {
             mutation_type,
             icon_index,
+            rarity,
+            rank: 1,
+            max_rank,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SynergyType {
+    Bloodbath,
+    Pinball,
+    Berserker,
+}
+
+// A bonus unlocked by holding a pair of mutations
+#[derive(Debug, PartialEq, Clone)]
+pub struct Synergy {
+    pub synergy_type: SynergyType,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub mutations: [MutationType; 2],
+}
+
+impl Synergy {
+    fn new(
+        synergy_type: SynergyType,
+        name: &'static str,
+        description: &'static str,
+        mutations: [MutationType; 2],
+    ) -> Self {
+        Self {
+            synergy_type,
+            name,
+            description,
+            mutations,
         }
     }
 }
@@ -122,4 +334,95 @@ pub fn mutation_manager_setup(_commands: Commands) {}
 
 fn mutation_manager_cleanup(_commands: Commands, mut mutation_manager: ResMut<MutationManager>) {
     mutation_manager.player_mutations.clear();
+    mutation_manager.banned.clear();
+    mutation_manager.screens_since_rare = 0;
+    mutation_manager.rare_guaranteed = false;
+}
+
+pub fn boss_rare_offer_system(
+    mut mutation_manager: ResMut<MutationManager>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+) {
+    let boss_killed = enemy_killed.iter().any(|killed| {
+        killed
+            .enemy_type
+            .as_ref()
+            .map_or(false, |enemy_type| enemy_type.is_boss())
+    });
+    if boss_killed {
+        mutation_manager.rare_guaranteed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn is_rare(mutation: &Mutation) -> bool {
+        mutation.rarity == Rarity::Rare
+    }
+
+    #[test]
+    fn banned_mutations_are_never_offered() {
+        let mut manager = MutationManager::new();
+        manager.ban_mutation(MutationType::Vampirism);
+        manager.ban_mutation(MutationType::DrySkin);
+        manager.add_mutation(MutationType::Rage);
+        manager.ban_mutation(MutationType::Rage);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let offers = manager.roll_offers(false, &mut rng);
+            assert!(offers
+                .iter()
+                .all(|m| !manager.banned.contains(&m.mutation_type)));
+        }
+    }
+
+    #[test]
+    fn pity_timer_puts_a_rare_first() {
+        let mut manager = MutationManager::new();
+        manager.screens_since_rare = PITY_THRESHOLD;
+
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..100 {
+            assert!(is_rare(&manager.roll_offers(false, &mut rng)[0]));
+        }
+    }
+
+    #[test]
+    fn guaranteed_rare_comes_first() {
+        let manager = MutationManager::new();
+
+        let mut rng = StdRng::seed_from_u64(13);
+        for _ in 0..100 {
+            assert!(is_rare(&manager.roll_offers(true, &mut rng)[0]));
+        }
+    }
+
+    #[test]
+    fn no_rare_first_without_pity_or_guarantee() {
+        let manager = MutationManager::new();
+
+        // Rares are a small share of the pool, so some seeded roll leads with something else
+        let mut rng = StdRng::seed_from_u64(17);
+        assert!((0..100).any(|_| !is_rare(&manager.roll_offers(false, &mut rng)[0])));
+    }
+
+    #[test]
+    fn record_offers_tracks_screens_since_rare() {
+        let mut manager = MutationManager::new();
+
+        manager.record_offers(&[MutationType::DrySkin, MutationType::Rage]);
+        manager.record_offers(&[MutationType::Shrink]);
+        assert_eq!(manager.screens_since_rare, 2);
+
+        manager.record_offers(&[MutationType::DrySkin, MutationType::Lasers]);
+        assert_eq!(manager.screens_since_rare, 0);
+
+        manager.record_offers(&[]);
+        assert_eq!(manager.screens_since_rare, 1);
+    }
 }
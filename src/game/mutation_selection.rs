@@ -3,19 +3,31 @@ use bevy::prelude::*;
 use bevy_kira_audio::AudioChannel;
 use bevy_kira_audio::AudioControl;
 use bevy_mod_ui_texture_atlas_image::{AtlasImageBundle, UiAtlasImage};
-use rand::seq::SliceRandom;
 
 use super::{
+    level_manager::LevelManager,
     mutation_manager::{Mutation, MutationManager, MutationType},
-    GameState,
+    pickup::Mutagen,
+    shop::Shop,
+    ui::spawn_rank_pips,
+    GameMode, GameState,
 };
 
+const BUTTON_POSITIONS: [Vec2; 5] = [
+    Vec2::new(225.0, 310.0),
+    Vec2::new(1270.0, 70.0),
+    Vec2::new(1570.0, 520.0),
+    Vec2::new(600.0, 700.0),
+    Vec2::new(1100.0, 700.0),
+];
+
 pub struct MutationSelectionPlugin;
 
 impl Plugin for MutationSelectionPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_system(mutation_selection_setup.in_schedule(OnEnter(GameState::MutationSelection)))
             .add_system(mutation_selection_system.in_set(OnUpdate(GameState::MutationSelection)))
+            .add_system(mutagen_spend_system.in_set(OnUpdate(GameState::MutationSelection)))
             .add_system(
                 mutation_selection_cleanup.in_schedule(OnExit(GameState::MutationSelection)),
             )
@@ -30,18 +42,86 @@ pub struct MutationSelectionData {
     pub bg_container: Entity,
     pub button_entities: Vec<Entity>,
     pub offered_mutations: Vec<(Entity, MutationType)>,
+    // Offers mutagen can still pull in, and what's needed to show them
+    pub remaining_mutations: Vec<Mutation>,
+    pub icon_atlas: Handle<TextureAtlas>,
+    pub mutagen_button: Option<Entity>,
+}
+
+impl MutationSelectionData {
+    // Adds an offer in the next free spot
+    pub fn push_offer(&mut self, commands: &mut Commands, mutation: Mutation) {
+        let button_entity = spawn_image_button(
+            commands,
+            &BUTTON_POSITIONS[self.offered_mutations.len()],
+            self.icon_atlas.clone(),
+            mutation.icon_index,
+        );
+        // A glow behind the icon shows how rare it is, and pips the rank it'd be taken at
+        commands
+            .entity(button_entity)
+            .insert((
+                MutationOffer(mutation.mutation_type),
+                BackgroundColor(mutation.rarity.color().with_a(0.25)),
+            ))
+            .with_children(|parent| {
+                spawn_rank_pips(parent, mutation.rank, mutation.max_rank, 16.0, 228.0);
+            });
+        self.button_entities.push(button_entity);
+        self.offered_mutations
+            .push((button_entity, mutation.mutation_type));
+    }
+
+    // Swaps whatever is on offer for the first `count` of `mutations`, the rest can still be
+    // pulled in with mutagen
+    pub fn set_offers(
+        &mut self,
+        commands: &mut Commands,
+        mut mutations: Vec<Mutation>,
+        count: usize,
+    ) {
+        for (entity, _) in self.offered_mutations.drain(..) {
+            commands.entity(entity).despawn_recursive();
+            self.button_entities
+                .retain(|button_entity| *button_entity != entity);
+        }
+
+        let count = count.min(BUTTON_POSITIONS.len());
+        self.remaining_mutations = mutations.split_off(count.min(mutations.len()));
+        for mutation in mutations {
+            self.push_offer(commands, mutation);
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct MutationSelectionUiNode;
 
+// A button that picks this mutation
+#[derive(Component)]
+pub struct MutationOffer(pub MutationType);
+
+#[derive(Component)]
+pub struct MutagenButton;
+
+#[derive(Component)]
+pub struct MutagenText;
+
+fn mutagen_label(mutagen: u32) -> String {
+    format!("Spend mutagen for another offer ({} left)", mutagen)
+}
+
 pub fn mutation_selection_setup(
     mut commands: Commands,
     ui_assets: Res<UiAssets>,
-    mutation_manager: Res<MutationManager>,
+    mut mutation_manager: ResMut<MutationManager>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     background: Res<AudioChannel<Background>>,
     music_assets: Res<MusicAssets>,
+    level_manager: Option<Res<LevelManager>>,
+    mutagen: Res<Mutagen>,
+    mut shop: ResMut<Shop>,
+    asset_server: Res<AssetServer>,
 ) {
     background.stop();
     background.play(music_assets.mutate.clone()).looped();
@@ -50,21 +130,30 @@ pub fn mutation_selection_setup(
         TextureAtlas::from_grid(mutation_icon_image, 16. * Vec2::ONE, 17, 1, None, None);
     let mutation_icon_texture_atlas_handle = texture_atlases.add(mutation_icon_texture_atlas);
 
-    // Generate 3 unique random mutations that the player hasn't acquired
-    let mut rng = rand::thread_rng();
-    let mut available_mutations: Vec<Mutation> = mutation_manager.unselected_mutations();
-    available_mutations.shuffle(&mut rng);
+    // Harder paths can offer extra mutations on top of the usual 3
+    let bonus_offers = level_manager
+        .map(|level_manager| level_manager.bonus_offers)
+        .unwrap_or(0);
+    let offer_count = (3 + bonus_offers).min(BUTTON_POSITIONS.len());
 
-    let offered_mutations = available_mutations
-        .into_iter()
-        .take(3)
-        .collect::<Vec<Mutation>>();
+    // Generate unique random mutations that the player hasn't acquired, weighted by rarity.
+    // Beating a boss guarantees a rare one
+    let mut rng = rand::thread_rng();
+    let guarantee_rare = mutation_manager.rare_guaranteed;
+    mutation_manager.rare_guaranteed = false;
+    let mut available_mutations: Vec<Mutation> =
+        mutation_manager.roll_offers(guarantee_rare, &mut rng);
 
-    let button_positions = vec![
-        Vec2::new(225.0, 310.0),
-        Vec2::new(1270.0, 70.0),
-        Vec2::new(1570.0, 520.0),
-    ];
+    // A mutation locked at the shop last time is always offered
+    if let Some(locked) = shop.locked.take() {
+        if let Some(index) = available_mutations
+            .iter()
+            .position(|m| m.mutation_type == locked)
+        {
+            let mutation = available_mutations.remove(index);
+            available_mutations.insert(0, mutation);
+        }
+    }
 
     // BG image
     let bg_container = commands
@@ -103,28 +192,51 @@ pub fn mutation_selection_setup(
         })
         .id();
 
-    let mut button_entities = Vec::new();
-
-    for (i, mutation) in offered_mutations.iter().enumerate() {
-        let button_entity = spawn_image_button(
-            &mut commands,
-            &button_positions[i],
-            mutation_icon_texture_atlas_handle.clone(),
-            mutation.icon_index,
-        );
-        button_entities.push(button_entity);
-    }
+    // Mutagen picked up during the run can buy extra offers
+    let mutagen_button = (mutagen.0 > 0).then(|| {
+        commands
+            .spawn(ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(30.0),
+                        left: Val::Px(30.0),
+                        ..default()
+                    },
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::rgba(0.4, 0.1, 0.5, 0.8)),
+                ..default()
+            })
+            .insert(MutagenButton)
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section(
+                        mutagen_label(mutagen.0),
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    MutagenText,
+                ));
+            })
+            .id()
+    });
 
     // Update the MutationSelectionData resource
-    commands.insert_resource(MutationSelectionData {
+    let mut menu_data = MutationSelectionData {
         bg_container,
-        button_entities: button_entities.clone(),
-        offered_mutations: offered_mutations
-            .into_iter()
-            .enumerate()
-            .map(|(i, mutation)| (button_entities[i], mutation.mutation_type))
-            .collect(),
-    });
+        button_entities: Vec::new(),
+        offered_mutations: Vec::new(),
+        remaining_mutations: Vec::new(),
+        icon_atlas: mutation_icon_texture_atlas_handle,
+        mutagen_button,
+    };
+    menu_data.set_offers(&mut commands, available_mutations, offer_count);
+    commands.insert_resource(menu_data);
 }
 
 fn spawn_image_button(
@@ -175,10 +287,27 @@ pub struct SineWaveMovementButton {
 
 pub fn mutation_selection_system(
     mut next_state: ResMut<NextState<GameState>>,
-    mut interaction_query: Query<(&Interaction, Entity), (Changed<Interaction>, With<Button>)>,
+    mut interaction_query: Query<
+        (&Interaction, Entity),
+        (Changed<Interaction>, With<MutationOffer>),
+    >,
     menu_data: Res<MutationSelectionData>,
     mut mutation_manager: ResMut<MutationManager>,
+    game_mode: Res<GameMode>,
 ) {
+    // The arena is still loaded, so go straight back to it
+    let next = if *game_mode == GameMode::Arena {
+        GameState::InLevel
+    } else {
+        GameState::SetupLevel
+    };
+
+    // Every mutation is either banned or maxed out, so there's nothing to pick
+    if menu_data.offered_mutations.is_empty() {
+        next_state.set(next);
+        return;
+    }
+
     for (interaction, entity) in &mut interaction_query {
         match *interaction {
             Interaction::Clicked => {
@@ -189,25 +318,70 @@ pub fn mutation_selection_system(
                 {
                     mutation_manager.add_mutation(*mutation_type);
                 }
-                next_state.set(GameState::SetupLevel)
+                next_state.set(next);
             }
             _ => {}
         }
     }
 }
 
-pub fn mutation_selection_cleanup(mut commands: Commands, menu_data: Res<MutationSelectionData>) {
+// Each mutagen adds one more offer while there's room on screen
+pub fn mutagen_spend_system(
+    mut commands: Commands,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<MutagenButton>)>,
+    mut text_query: Query<&mut Text, With<MutagenText>>,
+    mut menu_data: ResMut<MutationSelectionData>,
+    mut mutagen: ResMut<Mutagen>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction != Interaction::Clicked || mutagen.0 == 0 {
+            continue;
+        }
+        if menu_data.offered_mutations.len() >= BUTTON_POSITIONS.len() {
+            continue;
+        }
+        // Next in the weighted order
+        if menu_data.remaining_mutations.is_empty() {
+            continue;
+        }
+        let mutation = menu_data.remaining_mutations.remove(0);
+
+        mutagen.0 -= 1;
+        menu_data.push_offer(&mut commands, mutation);
+
+        for mut text in text_query.iter_mut() {
+            text.sections[0].value = mutagen_label(mutagen.0);
+        }
+    }
+}
+
+pub fn mutation_selection_cleanup(
+    mut commands: Commands,
+    menu_data: Res<MutationSelectionData>,
+    mut mutation_manager: ResMut<MutationManager>,
+) {
+    let offered = menu_data
+        .offered_mutations
+        .iter()
+        .map(|(_, mutation_type)| *mutation_type)
+        .collect::<Vec<_>>();
+    mutation_manager.record_offers(&offered);
+
     commands.entity(menu_data.bg_container).despawn_recursive();
     for button_entity in &menu_data.button_entities {
         commands.entity(*button_entity).despawn_recursive();
     }
+    if let Some(mutagen_button) = menu_data.mutagen_button {
+        commands.entity(mutagen_button).despawn_recursive();
+    }
 }
 
 pub fn sine_wave_movement_ui_system(
     time: Res<Time>,
     mut query: Query<(&SineWaveMovementButton, &mut Style)>,
 ) {
-    let elapsed_time = time.elapsed_seconds();
+    // Real time, the arena pauses the game while mutations are picked
+    let elapsed_time = time.raw_elapsed_seconds();
     for (sine_wave_movement, mut style) in query.iter_mut() {
         style.position.top = Val::Px(
             sine_wave_movement.initial_position_top
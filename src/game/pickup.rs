@@ -0,0 +1,451 @@
+use std::{collections::HashMap, str::FromStr};
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    app_state::AppState,
+    entity::{
+        creature::{
+            damage_invulnerability_system, ChangeColor, Creature, EnemyKilled, Invulnerable,
+            Lifetime, Velocity,
+        },
+        player::{Dead, Player, Rage},
+        spawner::EnemyType,
+        ZSort,
+    },
+    PIXELS_PER_METER,
+};
+
+use super::{breakable::PropBroken, level_manager::LevelObject, GameState};
+
+// Pickups start drifting toward the player from this far away
+const MAGNET_RADIUS: f32 = PIXELS_PER_METER * 3.0;
+const MAGNET_SPEED: f32 = 320.0;
+const COLLECT_RADIUS: f32 = PIXELS_PER_METER * 0.75;
+
+const PICKUP_LIFETIME_SECONDS: f32 = 12.0;
+// Pickups blink for this long before they vanish
+const PICKUP_BLINK_SECONDS: f32 = 3.0;
+
+const HEALTH_ORB_AMOUNT: f32 = 24.0;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<DropTables>()
+            .init_resource::<Mutagen>()
+            .add_system(mutagen_reset.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(pickup_drop_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(pickup_magnet_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(
+                pickup_collect_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(pickup_magnet_system),
+            )
+            .add_system(pickup_blink_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(haste_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(
+                ironskin_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(damage_invulnerability_system),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickupKind {
+    HealthOrb,
+    // Grants a random temporary buff, see `BuffKind`
+    BuffCapsule,
+    // Spent at the mutation screen for extra offers
+    Mutagen,
+}
+
+impl FromStr for PickupKind {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<PickupKind, Self::Err> {
+        match input {
+            "HealthOrb" => Ok(PickupKind::HealthOrb),
+            "BuffCapsule" => Ok(PickupKind::BuffCapsule),
+            "Mutagen" => Ok(PickupKind::Mutagen),
+            _ => Err(()),
+        }
+    }
+}
+
+impl PickupKind {
+    fn color(&self) -> Color {
+        match self {
+            PickupKind::HealthOrb => Color::rgb(0.3, 1.0, 0.4),
+            PickupKind::BuffCapsule => Color::rgb(1.0, 0.8, 0.2),
+            PickupKind::Mutagen => Color::rgb(0.8, 0.3, 1.0),
+        }
+    }
+
+    fn path(&self) -> Path {
+        match self {
+            PickupKind::HealthOrb => GeometryBuilder::build_as(&shapes::Circle {
+                radius: 5.0,
+                center: Vec2::ZERO,
+            }),
+            PickupKind::BuffCapsule => GeometryBuilder::build_as(&shapes::Rectangle {
+                extents: Vec2::new(8.0, 14.0),
+                origin: RectangleOrigin::Center,
+            }),
+            PickupKind::Mutagen => GeometryBuilder::build_as(&shapes::Polygon {
+                points: vec![
+                    Vec2::new(0.0, 8.0),
+                    Vec2::new(6.0, 0.0),
+                    Vec2::new(0.0, -8.0),
+                    Vec2::new(-6.0, 0.0),
+                ],
+                closed: true,
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuffKind {
+    Haste,
+    Rage,
+    Ironskin,
+}
+
+impl BuffKind {
+    pub const ALL: [BuffKind; 3] = [BuffKind::Haste, BuffKind::Rage, BuffKind::Ironskin];
+}
+
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+}
+
+// Each entry is rolled on its own, so a kill can drop nothing or a bit of everything
+#[derive(Clone, Debug)]
+pub struct DropEntry {
+    pub kind: PickupKind,
+    pub chance: f32,
+    pub count: u32,
+}
+
+impl DropEntry {
+    pub fn new(kind: PickupKind, chance: f32, count: u32) -> Self {
+        Self {
+            kind,
+            chance,
+            count,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DropTable {
+    pub entries: Vec<DropEntry>,
+}
+
+impl DropTable {
+    pub fn new(health: f32, buff: f32, mutagen: f32) -> Self {
+        Self {
+            entries: vec![
+                DropEntry::new(PickupKind::HealthOrb, health, 1),
+                DropEntry::new(PickupKind::BuffCapsule, buff, 1),
+                DropEntry::new(PickupKind::Mutagen, mutagen, 1),
+            ],
+        }
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> Vec<PickupKind> {
+        self.entries
+            .iter()
+            .filter(|entry| rng.gen_range(0.0..1.0) < entry.chance)
+            .flat_map(|entry| std::iter::repeat(entry.kind).take(entry.count as usize))
+            .collect()
+    }
+}
+
+// What each enemy type drops when killed. Enemies without a type, like summons, use `untyped`
+#[derive(Resource, Clone, Debug)]
+pub struct DropTables {
+    pub tables: HashMap<EnemyType, DropTable>,
+    pub untyped: DropTable,
+}
+
+impl Default for DropTables {
+    fn default() -> Self {
+        let boss = DropTable {
+            entries: vec![
+                DropEntry::new(PickupKind::HealthOrb, 1.0, 3),
+                DropEntry::new(PickupKind::BuffCapsule, 1.0, 1),
+                DropEntry::new(PickupKind::Mutagen, 1.0, 2),
+            ],
+        };
+        let brute = DropTable {
+            entries: vec![
+                DropEntry::new(PickupKind::HealthOrb, 0.4, 2),
+                DropEntry::new(PickupKind::BuffCapsule, 0.1, 1),
+                DropEntry::new(PickupKind::Mutagen, 0.04, 1),
+            ],
+        };
+
+        Self {
+            tables: HashMap::from([
+                (EnemyType::Slimer, DropTable::new(0.15, 0.03, 0.01)),
+                (EnemyType::Mutant, DropTable::new(0.2, 0.05, 0.02)),
+                (EnemyType::Goblin, DropTable::new(0.15, 0.04, 0.015)),
+                (EnemyType::GoblinBrute, brute),
+                (EnemyType::Adept, DropTable::new(0.25, 0.08, 0.03)),
+                // They come in swarms
+                (EnemyType::Skuller, DropTable::new(0.05, 0.01, 0.005)),
+                (EnemyType::LabBoss, boss.clone()),
+                (EnemyType::Sorcerian, boss),
+            ]),
+            untyped: DropTable::default(),
+        }
+    }
+}
+
+impl DropTables {
+    pub fn table(&self, enemy_type: Option<&EnemyType>) -> &DropTable {
+        enemy_type
+            .and_then(|enemy_type| self.tables.get(enemy_type))
+            .unwrap_or(&self.untyped)
+    }
+}
+
+// Collected over a run and spent at the mutation screen
+#[derive(Resource, Default)]
+pub struct Mutagen(pub u32);
+
+pub fn mutagen_reset(mut mutagen: ResMut<Mutagen>) {
+    mutagen.0 = 0;
+}
+
+pub fn spawn_pickup(commands: &mut Commands, kind: PickupKind, position: Vec2, rng: &mut impl Rng) {
+    // Scatter a little so piles don't stack up on one spot
+    let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+    let speed = rng.gen_range(60.0..120.0);
+
+    commands.spawn((
+        ShapeBundle {
+            path: kind.path(),
+            transform: Transform::from_translation(position.extend(0.0)),
+            ..default()
+        },
+        Fill::color(kind.color()),
+        Stroke::new(Color::WHITE, 1.0),
+        Pickup { kind },
+        Velocity {
+            value: Vec2::from_angle(angle) * speed,
+        },
+        ZSort::default(),
+        Lifetime {
+            timer: Timer::from_seconds(PICKUP_LIFETIME_SECONDS, TimerMode::Once),
+        },
+        LevelObject,
+    ));
+}
+
+pub fn pickup_drop_system(
+    mut commands: Commands,
+    drop_tables: Res<DropTables>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    mut prop_broken: EventReader<PropBroken>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for killed in enemy_killed.iter() {
+        let drops = drop_tables.table(killed.enemy_type.as_ref()).roll(&mut rng);
+        for kind in drops {
+            spawn_pickup(&mut commands, kind, killed.position, &mut rng);
+        }
+    }
+
+    // Breakables name what they drop in LDtk
+    for broken in prop_broken.iter() {
+        let kind = broken
+            .drop
+            .as_ref()
+            .and_then(|drop| PickupKind::from_str(drop).ok());
+        if let Some(kind) = kind {
+            spawn_pickup(&mut commands, kind, broken.position, &mut rng);
+        }
+    }
+}
+
+pub fn pickup_magnet_system(
+    time: Res<Time>,
+    player_query: Query<&Transform, (With<Player>, Without<Dead>)>,
+    mut pickup_query: Query<(&Transform, &mut Velocity), (With<Pickup>, Without<Player>)>,
+) {
+    let player_position = player_query
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.truncate());
+
+    for (transform, mut velocity) in pickup_query.iter_mut() {
+        let offset = player_position
+            .map(|player_position| player_position - transform.translation.truncate());
+
+        match offset {
+            Some(offset) if offset.length() < MAGNET_RADIUS => {
+                // Faster the closer it gets
+                let pull = 1.0 - offset.length() / MAGNET_RADIUS;
+                velocity.value = offset.normalize_or_zero() * MAGNET_SPEED * (0.3 + pull);
+            }
+            _ => {
+                // Settle down after scattering
+                velocity.value *= (1.0 - 4.0 * time.delta_seconds()).max(0.0);
+            }
+        }
+    }
+}
+
+pub fn pickup_collect_system(
+    mut commands: Commands,
+    mut mutagen: ResMut<Mutagen>,
+    mut player_query: Query<
+        (Entity, &Transform, &mut Creature, Option<&mut Haste>),
+        (With<Player>, Without<Dead>),
+    >,
+    pickup_query: Query<(Entity, &Transform, &Pickup), Without<Player>>,
+) {
+    let (player, player_transform, mut creature, mut haste) = match player_query.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let mut rng = rand::thread_rng();
+
+    for (entity, transform, pickup) in pickup_query.iter() {
+        let distance = player_transform
+            .translation
+            .truncate()
+            .distance(transform.translation.truncate());
+        if distance > COLLECT_RADIUS {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+
+        let mut color = pickup.kind.color();
+        match pickup.kind {
+            PickupKind::HealthOrb => {
+                creature.health = (creature.health + HEALTH_ORB_AMOUNT).min(creature.max_health);
+            }
+            PickupKind::BuffCapsule => {
+                let buff = *BuffKind::ALL.choose(&mut rng).unwrap();
+                match buff {
+                    BuffKind::Haste => match haste.as_mut() {
+                        // Picking up another one just tops up the timer
+                        Some(haste) => haste.timer.reset(),
+                        None => {
+                            commands.entity(player).insert(Haste::new(8.0, 1.5));
+                        }
+                    },
+                    BuffKind::Rage => {
+                        commands.entity(player).insert(Rage {
+                            timer: Timer::from_seconds(6.0, TimerMode::Once),
+                        });
+                    }
+                    BuffKind::Ironskin => {
+                        commands.entity(player).insert((
+                            Ironskin {
+                                timer: Timer::from_seconds(4.0, TimerMode::Once),
+                            },
+                            Invulnerable,
+                        ));
+                        color = Color::rgb(0.6, 0.8, 1.0);
+                    }
+                }
+            }
+            PickupKind::Mutagen => {
+                mutagen.0 += 1;
+            }
+        }
+
+        commands.entity(player).insert(ChangeColor {
+            color,
+            timer: Timer::from_seconds(0.2, TimerMode::Once),
+        });
+    }
+}
+
+// Pickups about to run out blink
+pub fn pickup_blink_system(
+    time: Res<Time>,
+    mut pickup_query: Query<(&Lifetime, &mut Visibility), With<Pickup>>,
+) {
+    for (lifetime, mut visibility) in pickup_query.iter_mut() {
+        let remaining = lifetime.timer.remaining_secs();
+        *visibility =
+            if remaining < PICKUP_BLINK_SECONDS && (time.elapsed_seconds() * 8.0).sin() < 0.0 {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+    }
+}
+
+// Movement speed buff
+#[derive(Component)]
+pub struct Haste {
+    pub timer: Timer,
+    pub multiplier: f32,
+}
+
+impl Haste {
+    pub fn new(seconds: f32, multiplier: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+            multiplier,
+        }
+    }
+}
+
+pub fn haste_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut haste_query: Query<(Entity, &mut Haste, &mut Creature)>,
+    added_query: Query<(), Added<Haste>>,
+) {
+    for (entity, mut haste, mut creature) in haste_query.iter_mut() {
+        if added_query.contains(entity) {
+            creature.max_speed *= haste.multiplier;
+        }
+        if haste.timer.tick(time.delta()).finished() {
+            creature.max_speed /= haste.multiplier;
+            commands.entity(entity).remove::<Haste>();
+        }
+    }
+}
+
+// Can't be hurt until it wears off
+#[derive(Component)]
+pub struct Ironskin {
+    pub timer: Timer,
+}
+
+pub fn ironskin_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ironskin_query: Query<(
+        Entity,
+        &mut Ironskin,
+        &mut TextureAtlasSprite,
+        Option<&ChangeColor>,
+    )>,
+) {
+    for (entity, mut ironskin, mut sprite, change_color) in ironskin_query.iter_mut() {
+        if ironskin.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<(Ironskin, Invulnerable)>();
+            sprite.color = Color::WHITE;
+            continue;
+        }
+        if change_color.is_none() {
+            sprite.color = Color::rgb(0.6, 0.8, 1.0);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::{app_state::AppState, entity::creature::EnemyKilled};
+
+use super::GameState;
+
+pub struct RunStatsPlugin;
+
+impl Plugin for RunStatsPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<RunStats>()
+            .add_system(run_stats_reset.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(run_stats_time_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(run_stats_kill_system.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+// Tallies shown on the game over screen
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub time: f32,
+    pub kills: BTreeMap<&'static str, u32>,
+}
+
+impl RunStats {
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+}
+
+pub fn run_stats_reset(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+pub fn run_stats_time_system(time: Res<Time>, mut run_stats: ResMut<RunStats>) {
+    run_stats.time += time.delta_seconds();
+}
+
+pub fn run_stats_kill_system(
+    mut run_stats: ResMut<RunStats>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+) {
+    for enemy_killed in enemy_killed.iter() {
+        // Summoned enemies don't come from a spawner and have no type
+        let identifier = enemy_killed
+            .enemy_type
+            .as_ref()
+            .map(|enemy_type| enemy_type.identifier())
+            .unwrap_or("Other");
+        *run_stats.kills.entry(identifier).or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,372 @@
+use bevy::prelude::*;
+use bevy_mod_ui_texture_atlas_image::{AtlasImageBundle, UiAtlasImage};
+
+use crate::{
+    app_state::AppState,
+    entity::{creature::EnemyKilled, spawner::EnemyType},
+};
+
+use super::{
+    mutation_manager::{MutationManager, MutationType},
+    mutation_selection::{mutation_selection_setup, MutationOffer, MutationSelectionData},
+    GameState,
+};
+
+const REROLL_BASE_COST: u32 = 5;
+// Each reroll on the same screen costs this much more than the last
+const REROLL_COST_STEP: u32 = 5;
+const REMOVE_COST: u32 = 15;
+const LOCK_COST: u32 = 10;
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<Shop>()
+            .add_system(shop_reset.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(shop_earn_system.in_set(OnUpdate(AppState::InGame)))
+            .add_system(
+                shop_setup
+                    .in_schedule(OnEnter(GameState::MutationSelection))
+                    .after(mutation_selection_setup),
+            )
+            .add_system(shop_lock_button_system.in_set(OnUpdate(GameState::MutationSelection)))
+            .add_system(shop_button_system.in_set(OnUpdate(GameState::MutationSelection)))
+            .add_system(
+                shop_remove_row_system
+                    .in_set(OnUpdate(GameState::MutationSelection))
+                    .after(shop_button_system),
+            )
+            .add_system(
+                shop_text_system
+                    .in_set(OnUpdate(GameState::MutationSelection))
+                    .after(shop_button_system),
+            )
+            .add_system(shop_cleanup.in_schedule(OnExit(GameState::MutationSelection)));
+    }
+}
+
+// Coins earned from kills over a run, and what's been bought with them
+#[derive(Resource, Default)]
+pub struct Shop {
+    pub coins: u32,
+    // Offered at the next mutation screen no matter what
+    pub locked: Option<MutationType>,
+    // Rerolls on the current mutation screen
+    pub rerolls: u32,
+}
+
+impl Shop {
+    pub fn reroll_cost(&self) -> u32 {
+        REROLL_BASE_COST + REROLL_COST_STEP * self.rerolls
+    }
+
+    // Takes the coins if there are enough
+    pub fn spend(&mut self, cost: u32) -> bool {
+        if self.coins < cost {
+            return false;
+        }
+        self.coins -= cost;
+        true
+    }
+}
+
+fn lock_label(shop: &Shop, mutation_type: MutationType) -> String {
+    if shop.locked == Some(mutation_type) {
+        "Locked".to_string()
+    } else {
+        format!("Lock ({})", LOCK_COST)
+    }
+}
+
+fn kill_reward(enemy_type: Option<&EnemyType>) -> u32 {
+    match enemy_type {
+        Some(enemy_type) if enemy_type.is_boss() => 25,
+        Some(EnemyType::GoblinBrute) => 3,
+        Some(EnemyType::Adept) => 2,
+        Some(_) => 1,
+        // Summons don't pay out
+        None => 0,
+    }
+}
+
+#[derive(Resource)]
+pub struct ShopData {
+    pub panel: Entity,
+    pub remove_row: Entity,
+    pub player_mutations_version: Option<u32>,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum ShopButton {
+    Reroll,
+    Remove(MutationType),
+    Lock(MutationType),
+}
+
+#[derive(Component)]
+pub struct ShopCoinsText;
+
+#[derive(Component)]
+pub struct ShopRerollText;
+
+#[derive(Component)]
+pub struct ShopLockText(pub MutationType);
+
+pub fn shop_reset(mut shop: ResMut<Shop>) {
+    *shop = Shop::default();
+}
+
+pub fn shop_earn_system(mut shop: ResMut<Shop>, mut enemy_killed: EventReader<EnemyKilled>) {
+    for enemy_killed in enemy_killed.iter() {
+        shop.coins += kill_reward(enemy_killed.enemy_type.as_ref());
+    }
+}
+
+fn text_style(asset_server: &AssetServer, font_size: f32) -> TextStyle {
+    TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size,
+        color: Color::WHITE,
+    }
+}
+
+fn shop_button_bundle() -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            padding: UiRect::all(Val::Px(8.0)),
+            margin: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        background_color: BackgroundColor(Color::rgba(0.1, 0.3, 0.2, 0.8)),
+        ..default()
+    }
+}
+
+pub fn shop_setup(mut commands: Commands, asset_server: Res<AssetServer>, mut shop: ResMut<Shop>) {
+    shop.rerolls = 0;
+
+    let mut remove_row = None;
+    let panel = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(30.0),
+                    right: Val::Px(30.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::FlexEnd,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    format!("Coins: {}", shop.coins),
+                    text_style(&asset_server, 36.0),
+                ),
+                ShopCoinsText,
+            ));
+            parent
+                .spawn((shop_button_bundle(), ShopButton::Reroll))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            format!("Reroll ({})", shop.reroll_cost()),
+                            text_style(&asset_server, 26.0),
+                        ),
+                        ShopRerollText,
+                    ));
+                });
+            parent.spawn(TextBundle::from_section(
+                format!("Remove and ban a mutation ({})", REMOVE_COST),
+                text_style(&asset_server, 26.0),
+            ));
+            remove_row = Some(
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .id(),
+            );
+        })
+        .id();
+
+    commands.insert_resource(ShopData {
+        panel,
+        remove_row: remove_row.unwrap(),
+        player_mutations_version: None,
+    });
+}
+
+// Every offer gets a button under it to keep it around for next time
+pub fn shop_lock_button_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    shop: Res<Shop>,
+    offer_query: Query<(Entity, &MutationOffer), Added<MutationOffer>>,
+) {
+    for (entity, offer) in offer_query.iter() {
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: UiRect {
+                                top: Val::Px(256.0),
+                                left: Val::Px(64.0),
+                                ..default()
+                            },
+                            padding: UiRect::all(Val::Px(6.0)),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(Color::rgba(0.1, 0.3, 0.2, 0.8)),
+                        ..default()
+                    },
+                    ShopButton::Lock(offer.0),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            lock_label(&shop, offer.0),
+                            text_style(&asset_server, 22.0),
+                        ),
+                        ShopLockText(offer.0),
+                    ));
+                });
+        });
+    }
+}
+
+pub fn shop_button_system(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &ShopButton), Changed<Interaction>>,
+    mut shop: ResMut<Shop>,
+    mut mutation_manager: ResMut<MutationManager>,
+    mut menu_data: ResMut<MutationSelectionData>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match *button {
+            ShopButton::Reroll => {
+                let cost = shop.reroll_cost();
+                if !shop.spend(cost) {
+                    continue;
+                }
+                shop.rerolls += 1;
+
+                // Same number of offers, but a locked one stays put
+                let count = menu_data.offered_mutations.len();
+                let mut mutations = mutation_manager.roll_offers(false, &mut rand::thread_rng());
+                if let Some(index) = shop
+                    .locked
+                    .and_then(|locked| mutations.iter().position(|m| m.mutation_type == locked))
+                {
+                    let mutation = mutations.remove(index);
+                    mutations.insert(0, mutation);
+                }
+                menu_data.set_offers(&mut commands, mutations, count);
+            }
+            ShopButton::Remove(mutation_type) => {
+                // Gone for good, so it doesn't just get offered again
+                if shop.spend(REMOVE_COST) {
+                    mutation_manager.remove_mutation(mutation_type);
+                    mutation_manager.ban_mutation(mutation_type);
+                }
+            }
+            ShopButton::Lock(mutation_type) => {
+                if shop.locked != Some(mutation_type) && shop.spend(LOCK_COST) {
+                    shop.locked = Some(mutation_type);
+                }
+            }
+        }
+    }
+}
+
+// One button per owned mutation, rebuilt whenever one gets removed
+pub fn shop_remove_row_system(
+    mut commands: Commands,
+    mut shop_data: ResMut<ShopData>,
+    mutation_manager: Res<MutationManager>,
+    menu_data: Res<MutationSelectionData>,
+) {
+    if shop_data.player_mutations_version == Some(mutation_manager.player_mutations_version) {
+        return;
+    }
+    shop_data.player_mutations_version = Some(mutation_manager.player_mutations_version);
+
+    let remove_row = shop_data.remove_row;
+    commands.entity(remove_row).despawn_descendants();
+    commands.entity(remove_row).with_children(|parent| {
+        for mutation in mutation_manager.player_mutations.iter() {
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(48.0), Val::Px(48.0)),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(Color::rgba(0.5, 0.1, 0.1, 0.8)),
+                        ..default()
+                    },
+                    ShopButton::Remove(mutation.mutation_type),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(AtlasImageBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(48.0), Val::Px(48.0)),
+                            ..default()
+                        },
+                        atlas_image: UiAtlasImage::new(
+                            menu_data.icon_atlas.clone(),
+                            mutation.icon_index,
+                        ),
+                        ..default()
+                    });
+                });
+        }
+    });
+}
+
+pub fn shop_text_system(
+    shop: Res<Shop>,
+    mut coins_query: Query<&mut Text, (With<ShopCoinsText>, Without<ShopRerollText>)>,
+    mut reroll_query: Query<&mut Text, (With<ShopRerollText>, Without<ShopCoinsText>)>,
+    mut lock_query: Query<
+        (&mut Text, &ShopLockText),
+        (Without<ShopCoinsText>, Without<ShopRerollText>),
+    >,
+) {
+    if !shop.is_changed() {
+        return;
+    }
+
+    for mut text in coins_query.iter_mut() {
+        text.sections[0].value = format!("Coins: {}", shop.coins);
+    }
+    for mut text in reroll_query.iter_mut() {
+        text.sections[0].value = format!("Reroll ({})", shop.reroll_cost());
+    }
+    for (mut text, lock) in lock_query.iter_mut() {
+        text.sections[0].value = lock_label(&shop, lock.0);
+    }
+}
+
+pub fn shop_cleanup(mut commands: Commands, shop_data: Res<ShopData>) {
+    commands.entity(shop_data.panel).despawn_recursive();
+    commands.remove_resource::<ShopData>();
+}
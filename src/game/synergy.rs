@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+
+use crate::{
+    app_state::AppState,
+    entity::{
+        creature::{deal_damage_system, Bleed, ChangeColor, Creature, EnemyKilled, Lifetime},
+        player::{Dead, Player},
+    },
+};
+
+use super::{
+    mutation_manager::{MutationManager, SynergyType},
+    mutation_selection::MutationOffer,
+    GameState,
+};
+
+const SYNERGY_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+const BLOODBATH_HEAL: f32 = 8.0;
+
+pub struct SynergyPlugin;
+
+impl Plugin for SynergyPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        app.init_resource::<SynergyTracker>()
+            .add_system(synergy_reset.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(synergy_notification_system.in_set(OnUpdate(GameState::InLevel)))
+            .add_system(synergy_hint_system.in_set(OnUpdate(GameState::MutationSelection)))
+            .add_system(
+                bloodbath_system
+                    .in_set(OnUpdate(GameState::InLevel))
+                    .after(deal_damage_system),
+            );
+    }
+}
+
+// Synergies already announced this run
+#[derive(Resource, Default)]
+pub struct SynergyTracker {
+    pub player_mutations_version: u32,
+    pub announced: Vec<SynergyType>,
+}
+
+pub fn synergy_reset(mut tracker: ResMut<SynergyTracker>) {
+    *tracker = SynergyTracker::default();
+}
+
+// Pops up a banner the first time a synergy unlocks
+pub fn synergy_notification_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mutation_manager: Res<MutationManager>,
+    mut tracker: ResMut<SynergyTracker>,
+) {
+    if tracker.player_mutations_version == mutation_manager.player_mutations_version {
+        return;
+    }
+    tracker.player_mutations_version = mutation_manager.player_mutations_version;
+
+    // Removing a mutation at the shop loses the synergy, so it gets announced again if it comes back
+    let active = mutation_manager
+        .active_synergies()
+        .map(|synergy| synergy.synergy_type)
+        .collect::<Vec<_>>();
+    tracker
+        .announced
+        .retain(|synergy_type| active.contains(synergy_type));
+
+    for synergy in mutation_manager.active_synergies() {
+        if tracker.announced.contains(&synergy.synergy_type) {
+            continue;
+        }
+        tracker.announced.push(synergy.synergy_type);
+
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(120.0),
+                        left: Val::Px(0.0),
+                        right: Val::Px(0.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Lifetime {
+                timer: Timer::from_seconds(3.0, TimerMode::Once),
+            })
+            .with_children(|parent| {
+                let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+                parent.spawn(TextBundle::from_section(
+                    format!("Synergy: {}", synergy.name),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 48.0,
+                        color: SYNERGY_COLOR,
+                    },
+                ));
+                parent.spawn(TextBundle::from_section(
+                    synergy.description,
+                    TextStyle {
+                        font,
+                        font_size: 28.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            });
+    }
+}
+
+// Offers that would complete a synergy say so above the icon
+pub fn synergy_hint_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mutation_manager: Res<MutationManager>,
+    offer_query: Query<(Entity, &MutationOffer), Added<MutationOffer>>,
+) {
+    for (entity, offer) in offer_query.iter() {
+        let synergies = mutation_manager.synergies_completed_by(offer.0);
+        if synergies.is_empty() {
+            continue;
+        }
+
+        let names = synergies
+            .iter()
+            .map(|synergy| synergy.name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(-36.0),
+                        left: Val::Px(0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ..TextBundle::from_section(
+                    format!("Synergy: {}", names),
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 28.0,
+                        color: SYNERGY_COLOR,
+                    },
+                )
+            });
+        });
+    }
+}
+
+// Hemophilia + Vampirism, enemies that die while bleeding heal the player
+pub fn bloodbath_system(
+    mut commands: Commands,
+    mutation_manager: Res<MutationManager>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    bleed_query: Query<(), With<Bleed>>,
+    mut player_query: Query<(Entity, &mut Creature), (With<Player>, Without<Dead>)>,
+) {
+    if !mutation_manager.has_synergy(SynergyType::Bloodbath) {
+        enemy_killed.clear();
+        return;
+    }
+    let (player, mut creature) = match player_query.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    for killed in enemy_killed.iter() {
+        if !bleed_query.contains(killed.entity) {
+            continue;
+        }
+        creature.health = (creature.health + BLOODBATH_HEAL).min(creature.max_health);
+        commands.entity(player).insert(ChangeColor {
+            color: Color::GREEN,
+            timer: Timer::from_seconds(0.2, TimerMode::Once),
+        });
+    }
+}
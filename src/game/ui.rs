@@ -3,7 +3,11 @@ use bevy_mod_ui_texture_atlas_image::{AtlasImageBundle, UiAtlasImage};
 
 use crate::{
     app_state::{loading::UiAssets, AppState},
-    entity::{creature::Creature, player::Player},
+    entity::{
+        ability::{AbilitySlot, ActiveAbility},
+        creature::Creature,
+        player::Player,
+    },
 };
 
 use super::mutation_manager::{mutation_manager_setup, MutationManager};
@@ -16,6 +20,8 @@ pub struct UiState {
     ui_root_node: Entity,
     last_index: usize,
     last_color: Color,
+    // What the ability icon was last drawn with, the ability and its charges out of its max
+    last_ability: Option<(ActiveAbility, u32, u32)>,
 }
 
 impl Plugin for UiPlugin {
@@ -31,6 +37,11 @@ impl Plugin for UiPlugin {
                 .in_set(OnUpdate(AppState::InGame))
                 .after(ui_setup)
                 .after(mutation_manager_setup),
+        )
+        .add_system(
+            ability_ui_system
+                .in_set(OnUpdate(AppState::InGame))
+                .after(ui_setup),
         );
     }
 }
@@ -66,6 +77,7 @@ fn ui_setup(
         ui_root_node: ui_entity,
         last_index: 0,
         last_color: Color::default(),
+        last_ability: None,
     });
 }
 
@@ -79,6 +91,60 @@ pub struct MutationIcon;
 #[derive(Component)]
 pub struct HealthIcon;
 
+// Both mutations of an active synergy on a gold backing
+#[derive(Component)]
+pub struct SynergyIcon;
+
+// The active ability's mutation on a backing in the ability's color, with a pip per charge
+#[derive(Component)]
+pub struct AbilityIcon;
+
+// Darkens the ability icon from the top while a charge is coming back
+#[derive(Component)]
+pub struct AbilityCooldown;
+
+// A row of pips under a mutation icon, filled up to its rank. Nothing for mutations that
+// can't rank up
+pub fn spawn_rank_pips(parent: &mut ChildBuilder, rank: u32, max_rank: u32, size: f32, top: f32) {
+    if max_rank <= 1 {
+        return;
+    }
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(top),
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    ..default()
+                },
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for i in 0..max_rank {
+                let color = if i < rank {
+                    Color::rgb(1.0, 0.9, 0.3)
+                } else {
+                    Color::rgba(0.2, 0.2, 0.2, 0.8)
+                };
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(size), Val::Px(size)),
+                        margin: UiRect::horizontal(Val::Px(size / 4.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(color),
+                    ..default()
+                });
+            }
+        });
+}
+
 fn health_icon(creature_health: f32, max_health: f32) -> usize {
     // Clamp the creature's health between 0 and max_health
     let clamped_health = creature_health.min(max_health).max(0.0);
@@ -103,7 +169,7 @@ fn ui_system(
     ui_assets: Res<UiAssets>,
     mut ui_state: ResMut<UiState>,
     mutation_manager: Res<MutationManager>,
-    mut icon_query: Query<Entity, With<MutationIcon>>,
+    mut icon_query: Query<Entity, Or<(With<MutationIcon>, With<SynergyIcon>)>>,
     mut health_icon_query: Query<Entity, With<HealthIcon>>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     mut player_query: Query<&mut Creature, With<Player>>,
@@ -199,7 +265,161 @@ fn ui_system(
                     atlas_image: UiAtlasImage::new(texture_atlas_handle.clone(), icon_index),
                     ..default()
                 })
-                .insert(MutationIcon);
+                .insert(MutationIcon)
+                .with_children(|parent| {
+                    spawn_rank_pips(parent, mutation.rank, mutation.max_rank, 6.0, 34.0);
+                });
+        });
+    }
+
+    let mutation_count = mutation_manager.player_mutations.len();
+    for (i, synergy) in mutation_manager.active_synergies().enumerate() {
+        let icon_indices = synergy.mutations.map(|mutation_type| {
+            mutation_manager
+                .all_mutations
+                .iter()
+                .find(|m| m.mutation_type == mutation_type)
+                .map(|m| m.icon_index)
+                .unwrap_or(0)
         });
+
+        commands.entity(container).with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(40.0), Val::Px(24.0)),
+                        margin: UiRect {
+                            left: Val::Px(40.0 * (mutation_count + i) as f32),
+                            ..default()
+                        },
+                        justify_content: JustifyContent::SpaceEvenly,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: BackgroundColor(Color::rgb(1.0, 0.8, 0.2)),
+                    ..default()
+                })
+                .insert(SynergyIcon)
+                .with_children(|parent| {
+                    for icon_index in icon_indices {
+                        parent.spawn(AtlasImageBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(16.0), Val::Px(16.0)),
+                                ..default()
+                            },
+                            atlas_image: UiAtlasImage::new(
+                                texture_atlas_handle.clone(),
+                                icon_index,
+                            ),
+                            ..default()
+                        });
+                    }
+                });
+        });
+    }
+}
+
+fn ability_ui_system(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    mut ui_state: ResMut<UiState>,
+    mutation_manager: Res<MutationManager>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    slot_query: Query<&AbilitySlot, With<Player>>,
+    icon_query: Query<Entity, With<AbilityIcon>>,
+    mut cooldown_query: Query<&mut Style, With<AbilityCooldown>>,
+) {
+    let slot = slot_query.get_single().ok();
+    let ability = slot.and_then(|slot| {
+        slot.ability
+            .map(|ability| (ability, slot.charges, slot.max_charges))
+    });
+
+    // Only rebuild the icon when the ability or its charges change
+    if ability != ui_state.last_ability {
+        ui_state.last_ability = ability;
+
+        for entity in icon_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        if let Some((ability, charges, max_charges)) = ability {
+            // Show the mutation that granted it
+            let icon_index = mutation_manager
+                .all_mutations
+                .iter()
+                .find(|m| ActiveAbility::granted_by(m.mutation_type) == Some(ability))
+                .map_or(0, |m| m.icon_index);
+
+            let image = ui_assets.mutation_icons.clone();
+            let texture_atlas = TextureAtlas::from_grid(image, 32. * Vec2::ONE, 17, 1, None, None);
+            let texture_atlas_handle = texture_atlases.add(texture_atlas);
+
+            // Sits at the start of the mutation icon row, out of the way of the icons' layout
+            commands
+                .entity(ui_state.ui_root_node)
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(40.0), Val::Px(40.0)),
+                                position_type: PositionType::Absolute,
+                                position: UiRect {
+                                    top: Val::Px(12.0),
+                                    left: Val::Px(16.0),
+                                    ..default()
+                                },
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: BackgroundColor(ability.color()),
+                            ..default()
+                        })
+                        .insert(AbilityIcon)
+                        .with_children(|parent| {
+                            parent.spawn(AtlasImageBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(32.0), Val::Px(32.0)),
+                                    ..default()
+                                },
+                                atlas_image: UiAtlasImage::new(texture_atlas_handle, icon_index),
+                                ..default()
+                            });
+                            parent.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Percent(100.0), Val::Percent(0.0)),
+                                        position_type: PositionType::Absolute,
+                                        position: UiRect {
+                                            top: Val::Px(0.0),
+                                            left: Val::Px(0.0),
+                                            ..default()
+                                        },
+                                        ..default()
+                                    },
+                                    background_color: BackgroundColor(Color::rgba(
+                                        0.0, 0.0, 0.0, 0.6,
+                                    )),
+                                    ..default()
+                                },
+                                AbilityCooldown,
+                            ));
+                            spawn_rank_pips(parent, charges, max_charges, 6.0, 42.0);
+                        });
+                });
+        }
+    }
+
+    // The overlay shrinks as the next charge comes back, and is gone at full charges
+    if let Some(slot) = slot {
+        let remaining = if slot.charges < slot.max_charges {
+            1.0 - slot.recharge.percent()
+        } else {
+            0.0
+        };
+        for mut style in cooldown_query.iter_mut() {
+            style.size.height = Val::Percent(remaining * 100.0);
+        }
     }
 }